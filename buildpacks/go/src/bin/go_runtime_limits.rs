@@ -0,0 +1,192 @@
+// Required due to: https://github.com/rust-lang/rust/issues/95513
+#![allow(unused_crate_dependencies)]
+
+//! An exec.d program that sets `GOMEMLIMIT` and `GOMAXPROCS` from the cgroup
+//! (v1 or v2) memory and CPU limits of the container at launch. Variables
+//! already set by the user are left untouched. `GOMAXPROCS` isn't set when
+//! the buildpack disables it, for Go versions whose runtime already follows
+//! the CPU limit.
+
+use libcnb::data::exec_d::ExecDProgramOutputKey;
+use libcnb::data::exec_d_program_output_key;
+use libcnb::exec_d::write_exec_d_program_output;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const MEMLIMIT_FRACTION_ENV: &str = "HEROKU_GOMEMLIMIT_FRACTION";
+const DEFAULT_MEMLIMIT_FRACTION: f64 = 0.9;
+const CGROUP_GOMAXPROCS_ENV: &str = "HEROKU_GO_CGROUP_GOMAXPROCS";
+
+// cgroup v1 reports "no limit" as a very large number (the max i64 rounded
+// down to the page size) rather than a sentinel value.
+const CGROUP_V1_UNLIMITED: u64 = 1 << 62;
+
+fn main() {
+    let cgroup_root = Path::new(CGROUP_ROOT);
+    let mut output: HashMap<ExecDProgramOutputKey, String> = HashMap::new();
+
+    if std::env::var_os("GOMEMLIMIT").is_none()
+        && let Some(limit) = memory_limit(cgroup_root)
+    {
+        let fraction = std::env::var(MEMLIMIT_FRACTION_ENV)
+            .ok()
+            .and_then(|value| parse_fraction(&value))
+            .unwrap_or(DEFAULT_MEMLIMIT_FRACTION);
+        output.insert(
+            exec_d_program_output_key!("GOMEMLIMIT"),
+            gomemlimit(limit, fraction).to_string(),
+        );
+    }
+
+    if std::env::var_os("GOMAXPROCS").is_none()
+        && std::env::var(CGROUP_GOMAXPROCS_ENV)
+            .ok()
+            .is_none_or(|value| value != "false")
+        && let Some(procs) = cpu_limit(cgroup_root)
+    {
+        output.insert(exec_d_program_output_key!("GOMAXPROCS"), procs.to_string());
+    }
+
+    write_exec_d_program_output(output);
+}
+
+/// Reads the container memory limit in bytes, preferring cgroup v2 over v1.
+/// Returns `None` if there is no limit or it can't be determined.
+fn memory_limit(cgroup_root: &Path) -> Option<u64> {
+    if let Ok(contents) = fs::read_to_string(cgroup_root.join("memory.max")) {
+        return contents.trim().parse::<u64>().ok();
+    }
+    fs::read_to_string(cgroup_root.join("memory/memory.limit_in_bytes"))
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u64>().ok())
+        .filter(|limit| *limit < CGROUP_V1_UNLIMITED)
+}
+
+/// Reads the container CPU quota as a whole number of CPUs, rounded up like
+/// the Go runtime does, preferring cgroup v2 over v1. Returns `None` if there is no quota or it
+/// can't be determined.
+fn cpu_limit(cgroup_root: &Path) -> Option<u64> {
+    let (quota, period) = if let Ok(contents) = fs::read_to_string(cgroup_root.join("cpu.max")) {
+        let mut parts = contents.split_whitespace();
+        (
+            parts.next()?.parse::<i64>().ok()?,
+            parts.next()?.parse::<i64>().ok()?,
+        )
+    } else {
+        let read = |file: &str| {
+            fs::read_to_string(cgroup_root.join("cpu").join(file))
+                .ok()
+                .and_then(|contents| contents.trim().parse::<i64>().ok())
+        };
+        (read("cpu.cfs_quota_us")?, read("cpu.cfs_period_us")?)
+    };
+    let quota = u64::try_from(quota).ok().filter(|quota| *quota > 0)?;
+    let period = u64::try_from(period).ok().filter(|period| *period > 0)?;
+    Some(quota.div_ceil(period))
+}
+
+/// Parses a memory limit fraction, which must be in the range (0, 1].
+fn parse_fraction(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|fraction| *fraction > 0.0 && *fraction <= 1.0)
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn gomemlimit(limit: u64, fraction: f64) -> u64 {
+    (limit as f64 * fraction) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cgroup(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for (path, contents) in files {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn memory_limit_v2() {
+        let root = cgroup(&[("memory.max", "536870912\n")]);
+        assert_eq!(memory_limit(root.path()), Some(536_870_912));
+
+        let root = cgroup(&[("memory.max", "max\n")]);
+        assert_eq!(memory_limit(root.path()), None);
+    }
+
+    #[test]
+    fn memory_limit_v1() {
+        let root = cgroup(&[("memory/memory.limit_in_bytes", "1073741824\n")]);
+        assert_eq!(memory_limit(root.path()), Some(1_073_741_824));
+
+        let root = cgroup(&[("memory/memory.limit_in_bytes", "9223372036854771712\n")]);
+        assert_eq!(memory_limit(root.path()), None);
+    }
+
+    #[test]
+    fn memory_limit_missing() {
+        let root = cgroup(&[]);
+        assert_eq!(memory_limit(root.path()), None);
+    }
+
+    #[test]
+    fn cpu_limit_v2() {
+        let root = cgroup(&[("cpu.max", "250000 100000\n")]);
+        assert_eq!(cpu_limit(root.path()), Some(3));
+
+        let root = cgroup(&[("cpu.max", "50000 100000\n")]);
+        assert_eq!(cpu_limit(root.path()), Some(1));
+
+        let root = cgroup(&[("cpu.max", "150000 100000\n")]);
+        assert_eq!(cpu_limit(root.path()), Some(2));
+
+        let root = cgroup(&[("cpu.max", "200000 100000\n")]);
+        assert_eq!(cpu_limit(root.path()), Some(2));
+
+        let root = cgroup(&[("cpu.max", "max 100000\n")]);
+        assert_eq!(cpu_limit(root.path()), None);
+    }
+
+    #[test]
+    fn cpu_limit_v1() {
+        let root = cgroup(&[
+            ("cpu/cpu.cfs_quota_us", "400000\n"),
+            ("cpu/cpu.cfs_period_us", "100000\n"),
+        ]);
+        assert_eq!(cpu_limit(root.path()), Some(4));
+
+        let root = cgroup(&[
+            ("cpu/cpu.cfs_quota_us", "-1\n"),
+            ("cpu/cpu.cfs_period_us", "100000\n"),
+        ]);
+        assert_eq!(cpu_limit(root.path()), None);
+    }
+
+    #[test]
+    fn fraction_parsing() {
+        assert_eq!(parse_fraction("0.75"), Some(0.75));
+        assert_eq!(parse_fraction("1"), Some(1.0));
+        assert_eq!(parse_fraction("0"), None);
+        assert_eq!(parse_fraction("1.5"), None);
+        assert_eq!(parse_fraction("lots"), None);
+    }
+
+    #[test]
+    fn gomemlimit_fraction() {
+        assert_eq!(gomemlimit(1_000_000_000, 0.9), 900_000_000);
+    }
+}
@@ -0,0 +1,410 @@
+use crate::cfg::BuildEnvConfig;
+use bullet_stream::global::print;
+use heroku_go_utils::vrs::GoVersion;
+use std::collections::BTreeMap;
+
+/// An experiment known to the go command, with the Go minor versions that
+/// added it and, once it became the default, removed it.
+struct Experiment {
+    name: &'static str,
+    added_in: u64,
+    removed_in: Option<u64>,
+}
+
+/// The newest Go minor version whose experiments are all in `EXPERIMENTS`.
+/// Experiments of newer versions may have been added or removed since, so
+/// they're only checked with a warning.
+const EXPERIMENTS_THROUGH: u64 = 27;
+
+/// Experiments known to the go command. Experiments are disabled with a `no`
+/// prefix.
+const EXPERIMENTS: [Experiment; 26] = [
+    Experiment {
+        name: "aliastypeparams",
+        added_in: 23,
+        removed_in: Some(25),
+    },
+    Experiment {
+        name: "allocheaders",
+        added_in: 22,
+        removed_in: Some(23),
+    },
+    Experiment {
+        name: "arenas",
+        added_in: 20,
+        removed_in: None,
+    },
+    Experiment {
+        name: "boringcrypto",
+        added_in: 19,
+        removed_in: None,
+    },
+    Experiment {
+        name: "cacheprog",
+        added_in: 21,
+        removed_in: Some(24),
+    },
+    Experiment {
+        name: "cgocheck2",
+        added_in: 21,
+        removed_in: None,
+    },
+    Experiment {
+        name: "coverageredesign",
+        added_in: 20,
+        removed_in: Some(25),
+    },
+    Experiment {
+        name: "dwarf5",
+        added_in: 25,
+        removed_in: None,
+    },
+    Experiment {
+        name: "exectracer2",
+        added_in: 22,
+        removed_in: Some(23),
+    },
+    Experiment {
+        name: "fieldtrack",
+        added_in: 0,
+        removed_in: None,
+    },
+    Experiment {
+        name: "goroutineleakprofile",
+        added_in: 26,
+        removed_in: None,
+    },
+    Experiment {
+        name: "greenteagc",
+        added_in: 25,
+        removed_in: None,
+    },
+    Experiment {
+        name: "heapminimum512kib",
+        added_in: 19,
+        removed_in: None,
+    },
+    Experiment {
+        name: "jsonv2",
+        added_in: 25,
+        removed_in: None,
+    },
+    Experiment {
+        name: "loopvar",
+        added_in: 21,
+        removed_in: None,
+    },
+    Experiment {
+        name: "newinliner",
+        added_in: 22,
+        removed_in: None,
+    },
+    Experiment {
+        name: "pagetrace",
+        added_in: 21,
+        removed_in: None,
+    },
+    Experiment {
+        name: "preemptibleloops",
+        added_in: 0,
+        removed_in: None,
+    },
+    Experiment {
+        name: "rangefunc",
+        added_in: 22,
+        removed_in: Some(24),
+    },
+    Experiment {
+        name: "runtimesecret",
+        added_in: 26,
+        removed_in: None,
+    },
+    Experiment {
+        name: "simd",
+        added_in: 26,
+        removed_in: None,
+    },
+    Experiment {
+        name: "spinbitmutex",
+        added_in: 24,
+        removed_in: None,
+    },
+    Experiment {
+        name: "staticlockranking",
+        added_in: 15,
+        removed_in: None,
+    },
+    Experiment {
+        name: "swissmap",
+        added_in: 24,
+        removed_in: None,
+    },
+    Experiment {
+        name: "synchashtriemap",
+        added_in: 24,
+        removed_in: None,
+    },
+    Experiment {
+        name: "synctest",
+        added_in: 24,
+        removed_in: None,
+    },
+];
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("{0} requires Go 1.{1} or newer, but {2} was resolved")]
+    Unsupported(String, u64, GoVersion),
+    #[error("{0} isn't supported since Go 1.{1}, but {2} was resolved")]
+    Removed(String, u64, GoVersion),
+    #[error("Invalid {0} level `{1}`, expected {2}")]
+    Level(&'static str, String, &'static str),
+}
+
+/// The `GOAMD64`, `GOARM64`, and `GOEXPERIMENT` environment variables for the
+/// configured microarchitecture levels and experiments, checked against the
+/// Go version that builds with them. Experiments that aren't known to this
+/// buildpack are passed through with a warning, and rejected by the go
+/// command if they don't exist. So are experiments that seem unsupported by
+/// a Go version newer than the known experiments.
+///
+/// # Errors
+///
+/// Will return an error for an invalid level, or a level or experiment that
+/// the Go version doesn't support.
+pub(crate) fn env_vars(
+    config: &BuildEnvConfig,
+    go_version: &GoVersion,
+) -> Result<BTreeMap<String, String>, Error> {
+    let mut vars = BTreeMap::new();
+    for (goarch, level) in &config.microarch {
+        let (key, since) = if goarch == "amd64" {
+            check_amd64_level(level)?;
+            ("GOAMD64", 18)
+        } else {
+            check_arm64_level(level)?;
+            ("GOARM64", 23)
+        };
+        check_supported(&format!("{key}={level}"), since, go_version)?;
+        vars.insert(key.to_string(), level.clone());
+    }
+
+    for experiment in &config.experiments {
+        let name = experiment.strip_prefix("no").unwrap_or(experiment);
+        match EXPERIMENTS.iter().find(|known| known.name == name) {
+            Some(known) => match check_experiment(known, experiment, go_version) {
+                Err(error) if at_least(go_version, EXPERIMENTS_THROUGH + 1) => {
+                    print::warning(format!(
+                        "{error}. The Go experiments known to this buildpack predate \
+                        {go_version}, so the go command will fail if it doesn't support it."
+                    ));
+                }
+                result => result?,
+            },
+            None => print::warning(format!(
+                "Unknown Go experiment {experiment}. The go command will fail if {go_version} \
+                doesn't support it."
+            )),
+        }
+    }
+    if !config.experiments.is_empty() {
+        vars.insert(String::from("GOEXPERIMENT"), config.experiments.join(","));
+    }
+    Ok(vars)
+}
+
+/// Check that a known experiment, or its `no` prefixed form, is supported by
+/// a Go version.
+fn check_experiment(
+    known: &Experiment,
+    experiment: &str,
+    go_version: &GoVersion,
+) -> Result<(), Error> {
+    let setting = format!("GOEXPERIMENT={experiment}");
+    check_supported(&setting, known.added_in, go_version)?;
+    match known.removed_in {
+        Some(removed_in) if at_least(go_version, removed_in) => {
+            Err(Error::Removed(setting, removed_in, go_version.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_supported(setting: &str, since: u64, go_version: &GoVersion) -> Result<(), Error> {
+    if at_least(go_version, since) {
+        Ok(())
+    } else {
+        Err(Error::Unsupported(
+            setting.to_string(),
+            since,
+            go_version.clone(),
+        ))
+    }
+}
+
+/// Whether a Go version is at least the `1.{minor}` release.
+fn at_least(go_version: &GoVersion, minor: u64) -> bool {
+    GoVersion::try_from(format!("1.{minor}"))
+        .is_ok_and(|minimum| go_version.major_release_version() >= minimum)
+}
+
+fn check_amd64_level(level: &str) -> Result<(), Error> {
+    if ["v1", "v2", "v3", "v4"].contains(&level) {
+        Ok(())
+    } else {
+        Err(Error::Level(
+            "GOAMD64",
+            level.to_string(),
+            "v1, v2, v3, or v4",
+        ))
+    }
+}
+
+/// Check an `arm64` level: `v8.{0-9}` or `v9.{0-5}`, optionally followed by
+/// `,lse` and `,crypto` features.
+fn check_arm64_level(level: &str) -> Result<(), Error> {
+    let mut parts = level.split(',');
+    let version = parts.next().unwrap_or_default();
+    let valid_version = match version.strip_prefix('v').and_then(|v| v.split_once('.')) {
+        Some(("8", minor)) => matches!(minor.as_bytes(), [b'0'..=b'9']),
+        Some(("9", minor)) => matches!(minor.as_bytes(), [b'0'..=b'5']),
+        _ => false,
+    };
+    if valid_version && parts.all(|feature| feature == "lse" || feature == "crypto") {
+        Ok(())
+    } else {
+        Err(Error::Level(
+            "GOARM64",
+            level.to_string(),
+            "v8.{0-9} or v9.{0-5}, optionally with ,lse and ,crypto",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inv::GoInventory;
+
+    fn go_version(version: &str) -> GoVersion {
+        GoVersion::try_from(version.to_string()).unwrap()
+    }
+
+    #[test]
+    fn build_env_vars() {
+        let config = BuildEnvConfig {
+            microarch: vec![
+                ("amd64".to_string(), "v2".to_string()),
+                ("arm64".to_string(), "v8.2,lse".to_string()),
+                ("amd64".to_string(), "v3".to_string()),
+            ],
+            experiments: vec!["greenteagc".to_string(), "noswissmap".to_string()],
+        };
+        assert_eq!(
+            env_vars(&config, &go_version("go1.25.0"))
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [
+                ("GOAMD64".to_string(), "v3".to_string()),
+                ("GOARM64".to_string(), "v8.2,lse".to_string()),
+                (
+                    "GOEXPERIMENT".to_string(),
+                    "greenteagc,noswissmap".to_string()
+                ),
+            ]
+        );
+        assert!(
+            env_vars(&BuildEnvConfig::default(), &go_version("go1.16"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unsupported_build_env_vars() {
+        for (microarch, experiment, version, expected) in [
+            (
+                ("arm64", "v8.2"),
+                None,
+                "go1.22.5",
+                "GOARM64=v8.2 requires Go 1.23 or newer, but go1.22.5 was resolved",
+            ),
+            (
+                ("amd64", "v3"),
+                Some("noswissmap"),
+                "go1.23.4",
+                "GOEXPERIMENT=noswissmap requires Go 1.24 or newer, but go1.23.4 was resolved",
+            ),
+            (
+                ("amd64", "v3"),
+                Some("rangefunc"),
+                "go1.24.1",
+                "GOEXPERIMENT=rangefunc isn't supported since Go 1.24, but go1.24.1 was resolved",
+            ),
+            (
+                ("amd64", "v3"),
+                Some("noallocheaders"),
+                "go1.23rc1",
+                "GOEXPERIMENT=noallocheaders isn't supported since Go 1.23, but go1.23rc1 was resolved",
+            ),
+            (
+                ("amd64", "v5"),
+                None,
+                "go1.23.4",
+                "Invalid GOAMD64 level `v5`, expected v1, v2, v3, or v4",
+            ),
+            (
+                ("arm64", "v9.6"),
+                None,
+                "go1.23.4",
+                "Invalid GOARM64 level `v9.6`, expected v8.{0-9} or v9.{0-5}, optionally with ,lse and ,crypto",
+            ),
+            (
+                ("arm64", "v8.0,sve"),
+                None,
+                "go1.23.4",
+                "Invalid GOARM64 level `v8.0,sve`, expected v8.{0-9} or v9.{0-5}, optionally with ,lse and ,crypto",
+            ),
+        ] {
+            let config = BuildEnvConfig {
+                microarch: vec![(microarch.0.to_string(), microarch.1.to_string())],
+                experiments: experiment.into_iter().map(ToString::to_string).collect(),
+            };
+            assert_eq!(
+                env_vars(&config, &go_version(version))
+                    .unwrap_err()
+                    .to_string(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn build_env_vars_newer_than_experiments() {
+        let config = BuildEnvConfig {
+            microarch: vec![],
+            experiments: vec!["rangefunc".to_string()],
+        };
+        let version = go_version(&format!("go1.{}.0", EXPERIMENTS_THROUGH + 1));
+        assert_eq!(
+            env_vars(&config, &version).unwrap().get("GOEXPERIMENT"),
+            Some(&"rangefunc".to_string())
+        );
+    }
+
+    #[test]
+    fn experiments_cover_inventory() {
+        let inventory = include_str!("../inventory.toml")
+            .parse::<GoInventory>()
+            .unwrap();
+        for artifact in &inventory.artifacts {
+            assert!(
+                !at_least(&artifact.version, EXPERIMENTS_THROUGH + 1),
+                "The inventory has {}, but EXPERIMENTS only covers Go 1.{EXPERIMENTS_THROUGH}. \
+                Add the experiments of newer Go versions, and update EXPERIMENTS_THROUGH.",
+                artifact.version
+            );
+        }
+    }
+}
@@ -0,0 +1,162 @@
+/// Build information embedded in a Go binary, as reported by
+/// `go version -m`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct BuildInfo {
+    pub(crate) binary: String,
+    pub(crate) go_version: String,
+    pub(crate) path: Option<String>,
+    pub(crate) main: Option<Module>,
+    pub(crate) deps: Vec<Module>,
+    pub(crate) settings: Vec<(String, String)>,
+}
+
+/// A module embedded in a Go binary. If the module was replaced, the
+/// replacement is the module that was actually built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Module {
+    pub(crate) path: String,
+    pub(crate) version: String,
+    pub(crate) sum: Option<String>,
+    pub(crate) replace: Option<Box<Module>>,
+}
+
+impl Module {
+    /// The module that was built, which is the replacement module, if any.
+    pub(crate) fn built(&self) -> &Module {
+        self.replace.as_deref().unwrap_or(self)
+    }
+}
+
+/// Parse the output of `go version -m` for one or more binaries. Each binary
+/// starts with a `{path}: {go version}` line, followed by tab-indented
+/// `path`, `mod`, `dep`, `=>` (replacement), and `build` lines. Unrecognized
+/// lines are ignored. The go version omits the ` X:{experiments}` suffix of
+/// binaries built with `GOEXPERIMENT`, which are listed in the
+/// `GOEXPERIMENT` build setting.
+pub(crate) fn parse_go_version_m(output: &str) -> Vec<BuildInfo> {
+    let mut infos: Vec<BuildInfo> = vec![];
+    for line in output.lines() {
+        if !line.starts_with('\t') {
+            if let Some((binary, go_version)) = line.rsplit_once(": ") {
+                infos.push(BuildInfo {
+                    binary: binary.to_string(),
+                    go_version: go_version
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    ..BuildInfo::default()
+                });
+            }
+            continue;
+        }
+        let Some(info) = infos.last_mut() else {
+            continue;
+        };
+        let fields = line
+            .trim_start_matches('\t')
+            .split('\t')
+            .collect::<Vec<_>>();
+        match fields.as_slice() {
+            ["path", path, ..] => info.path = Some((*path).to_string()),
+            ["mod", rest @ ..] => info.main = module(rest),
+            ["dep", rest @ ..] => info.deps.extend(module(rest)),
+            ["=>", rest @ ..] => {
+                if let (Some(replaced), Some(replacement)) = (info.deps.last_mut(), module(rest)) {
+                    replaced.replace = Some(Box::new(replacement));
+                }
+            }
+            ["build", setting, ..] => {
+                if let Some((key, value)) = setting.split_once('=') {
+                    info.settings.push((key.to_string(), value.to_string()));
+                } else {
+                    // Older toolchains report some flags without values,
+                    // such as `-trimpath`.
+                    info.settings.push(((*setting).to_string(), String::new()));
+                }
+            }
+            _ => (),
+        }
+    }
+    infos
+}
+
+fn module(fields: &[&str]) -> Option<Module> {
+    match fields {
+        [path, version, rest @ ..] => Some(Module {
+            path: (*path).to_string(),
+            version: (*version).to_string(),
+            sum: rest
+                .first()
+                .filter(|sum| !sum.is_empty())
+                .map(ToString::to_string),
+            replace: None,
+        }),
+        _ => None,
+    }
+}
+
+/// `go version -m` output for two binaries of a module, shared by the tests
+/// of the modules that read build information.
+#[cfg(test)]
+pub(crate) const GO_VERSION_M: &str = "\
+/layers/heroku_go/go_target/bin/server: go1.22.1
+\tpath\texample.com/app/cmd/server
+\tmod\texample.com/app\t(devel)\t
+\tdep\tgithub.com/gin-gonic/gin\tv1.9.1\th1:4idEAncQnU5cB7BeOkPtxjfCSye0AAm1R0RVIqJ+Jmg=
+\tdep\tgithub.com/pkg/errors\tv0.9.1
+\t=>\t../errors\t(devel)\t
+\tbuild\t-buildmode=exe
+\tbuild\t-trimpath
+\tbuild\tCGO_ENABLED=1
+\tbuild\tvcs.revision=abc123
+/layers/heroku_go/go_target/bin/worker: go1.22.1 X:rangefunc,noswissmap
+\tpath\texample.com/app/cmd/worker
+\tmod\texample.com/app\t(devel)\t
+\tdep\tgithub.com/gin-gonic/gin\tv1.9.1\th1:4idEAncQnU5cB7BeOkPtxjfCSye0AAm1R0RVIqJ+Jmg=
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_go_version_m_output() {
+        let infos = parse_go_version_m(GO_VERSION_M);
+        assert_eq!(infos.len(), 2);
+
+        let server = &infos[0];
+        assert_eq!(server.binary, "/layers/heroku_go/go_target/bin/server");
+        assert_eq!(server.go_version, "go1.22.1");
+        assert_eq!(server.path.as_deref(), Some("example.com/app/cmd/server"));
+        assert_eq!(
+            server.main,
+            Some(Module {
+                path: "example.com/app".to_string(),
+                version: "(devel)".to_string(),
+                sum: None,
+                replace: None,
+            })
+        );
+        assert_eq!(
+            server.deps[0].sum.as_deref(),
+            Some("h1:4idEAncQnU5cB7BeOkPtxjfCSye0AAm1R0RVIqJ+Jmg=")
+        );
+        assert_eq!(server.deps[1].path, "github.com/pkg/errors");
+        assert_eq!(server.deps[1].built().path, "../errors");
+        assert_eq!(
+            server.settings,
+            [
+                ("-buildmode".to_string(), "exe".to_string()),
+                ("-trimpath".to_string(), String::new()),
+                ("CGO_ENABLED".to_string(), "1".to_string()),
+                ("vcs.revision".to_string(), "abc123".to_string()),
+            ]
+        );
+
+        let worker = &infos[1];
+        assert_eq!(worker.binary, "/layers/heroku_go/go_target/bin/worker");
+        assert_eq!(worker.go_version, "go1.22.1");
+        assert_eq!(worker.deps.len(), 1);
+    }
+}
@@ -1,13 +1,155 @@
-use heroku_go_utils::vrs::parse_go_version_requirement;
-
+use crate::gomod::{GoMod, parse_godebug_setting};
+use crate::vuln::Severity;
+use bullet_stream::global::print;
+use bullet_stream::style;
+use heroku_go_utils::vrs::{GoVersion, parse_go_version_requirement};
 use std::fs;
 use std::io::{BufRead, BufReader};
-use std::path;
+use std::path::{self, Path, PathBuf};
+
+/// The number of Go distributions kept in the build cache, unless configured
+/// with the `// +heroku toolchainCacheSize {count}` directive.
+pub(crate) const DEFAULT_TOOLCHAIN_CACHE_SIZE: usize = 3;
 
 /// Represents buildpack configuration found in a project's `go.mod`.
 pub(crate) struct GoModConfig {
     pub(crate) packages: Option<Vec<String>>,
     pub(crate) version: Option<semver::VersionReq>,
+    pub(crate) processes: Vec<ProcessConfig>,
+    pub(crate) process_settings: Vec<ProcessSettings>,
+    pub(crate) register_procfile: bool,
+    pub(crate) web_package: Option<String>,
+    pub(crate) vuln: VulnConfig,
+    pub(crate) deny_licenses: Vec<String>,
+    pub(crate) reproducible: bool,
+    pub(crate) verify_modules: bool,
+    pub(crate) toolchain: ToolchainConfig,
+    pub(crate) cross_compile: Vec<Platform>,
+    pub(crate) build_cache: BuildCachePolicy,
+    pub(crate) build_env: BuildEnvConfig,
+    pub(crate) gomod: GoMod,
+}
+
+/// Vulnerability scanning configuration, declared with the
+/// `// +heroku vulnDB {path}`, `// +heroku vulnFailOn {severity}`, and
+/// `// +heroku vulnScanGoSum` directives.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct VulnConfig {
+    pub(crate) db: Option<PathBuf>,
+    pub(crate) fail_on: Option<Severity>,
+    pub(crate) scan_gosum: bool,
+}
+
+/// Go toolchain configuration, declared with the
+/// `// +heroku toolchainCacheSize {count}` and `// +heroku launchToolchain`
+/// directives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ToolchainConfig {
+    pub(crate) cache_size: usize,
+    pub(crate) launch: bool,
+}
+
+impl Default for ToolchainConfig {
+    fn default() -> Self {
+        Self {
+            cache_size: DEFAULT_TOOLCHAIN_CACHE_SIZE,
+            launch: false,
+        }
+    }
+}
+
+/// A platform to cross-compile packages for, declared with the
+/// `// +heroku crossCompile {goos}/{goarch}...` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Platform {
+    pub(crate) goos: String,
+    pub(crate) goarch: String,
+}
+
+impl Platform {
+    /// The name of the directory for binaries built for this platform, as
+    /// used by `go install`.
+    pub(crate) fn dir_name(&self) -> String {
+        format!("{}_{}", self.goos, self.goarch)
+    }
+}
+
+impl std::str::FromStr for Platform {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once('/') {
+            Some((goos, goarch))
+                if [goos, goarch].iter().all(|part| {
+                    !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric())
+                }) =>
+            {
+                Ok(Platform {
+                    goos: goos.to_string(),
+                    goarch: goarch.to_string(),
+                })
+            }
+            _ => Err(format!("expected {{goos}}/{{goarch}}, found {value}")),
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.goos, self.goarch)
+    }
+}
+
+/// Whether the Go build cache is cleared or kept when build settings, like
+/// flags or `CGO_ENABLED`, change, declared with the
+/// `// +heroku buildCacheOnChange {clear|keep}` directive.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BuildCachePolicy {
+    #[default]
+    Clear,
+    Keep,
+}
+
+impl std::str::FromStr for BuildCachePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "clear" => Ok(BuildCachePolicy::Clear),
+            "keep" => Ok(BuildCachePolicy::Keep),
+            _ => Err(format!("expected clear or keep, found {value}")),
+        }
+    }
+}
+
+/// Microarchitecture levels and experiments to build with, declared with the
+/// `// +heroku microarch {goarch}={level}...` and
+/// `// +heroku goExperiment {name}...` directives.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct BuildEnvConfig {
+    pub(crate) microarch: Vec<(String, String)>,
+    pub(crate) experiments: Vec<String>,
+}
+
+/// A launch process declared with a `// +heroku process` directive:
+/// `// +heroku process {name} [--default] [--dir={path}] {binary} [{arg}]...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcessConfig {
+    pub(crate) name: String,
+    pub(crate) binary: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) working_dir: Option<String>,
+    pub(crate) default: bool,
+}
+
+/// Settings for a launch process, declared with the
+/// `// +heroku processEnv {name} {key}={value}...` and
+/// `// +heroku processDir {name} {path}` directives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcessSettings {
+    pub(crate) name: String,
+    pub(crate) env: Vec<(String, String)>,
+    pub(crate) working_dir: Option<String>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -16,35 +158,696 @@ pub(crate) enum ReadGoModConfigError {
     Io(#[from] std::io::Error),
     #[error("Failed to parse go.mod configuration: {0}")]
     Version(#[from] semver::Error),
+    #[error("Invalid go.mod directive `// +heroku {0}`: {1}")]
+    Directive(String, String),
 }
 
 /// Build a `GoModConfig` from a `go.mod` file.
 ///
 /// # Errors
 ///
-/// Will return an error when the file cannot be read or the version strings
-/// within are not parseable.
+/// Will return an error when the file cannot be read, a `// +heroku` directive
+/// is invalid, or the version strings within are not parseable. Invalid go.mod
+/// statements are recorded as warnings instead.
 pub(crate) fn read_gomod_config<P: AsRef<path::Path>>(
     gomod_path: P,
 ) -> Result<GoModConfig, ReadGoModConfigError> {
-    let mut version: Option<semver::VersionReq> = None;
-    let mut packages: Option<Vec<String>> = None;
-    let file = fs::File::open(gomod_path)?;
-    for line_result in BufReader::new(file).lines() {
-        let line = line_result?;
-        let mut parts = line.split_whitespace().peekable();
-        match (parts.next(), parts.next(), parts.next(), parts.peek()) {
-            (Some("//"), Some("+heroku"), Some("install"), Some(_)) => {
-                packages = Some(parts.map(ToString::to_string).collect());
+    let gomod = GoMod::parse(&fs::read_to_string(gomod_path)?);
+    let version = gomod
+        .go
+        .as_ref()
+        .map(|vrs| parse_go_version_requirement(&format!("={vrs}")))
+        .transpose()?;
+    let mut config = GoModConfig {
+        packages: None,
+        version,
+        processes: vec![],
+        process_settings: vec![],
+        register_procfile: false,
+        web_package: None,
+        vuln: VulnConfig::default(),
+        deny_licenses: vec![],
+        reproducible: false,
+        verify_modules: false,
+        toolchain: ToolchainConfig::default(),
+        cross_compile: vec![],
+        build_cache: BuildCachePolicy::default(),
+        build_env: BuildEnvConfig::default(),
+        gomod,
+    };
+    for directive in config.gomod.heroku_directives.clone() {
+        apply_directive(&mut config, &directive)?;
+    }
+    Ok(config)
+}
+
+/// Apply a `// +heroku` directive to the configuration. Unknown directives
+/// are ignored.
+fn apply_directive(
+    config: &mut GoModConfig,
+    directive: &[String],
+) -> Result<(), ReadGoModConfigError> {
+    match directive {
+        [name, pkgs @ ..] if name == "install" && !pkgs.is_empty() => {
+            config.packages = Some(pkgs.to_vec());
+        }
+        [name, vrs, ..] if name == "goVersion" => {
+            config.version = parse_go_version_requirement(vrs).map(Some)?;
+        }
+        [name, args @ ..] if name == "process" => {
+            config
+                .processes
+                .push(parse_process_directive(args).map_err(|message| {
+                    ReadGoModConfigError::Directive(directive.join(" "), message)
+                })?);
+        }
+        [name, process, vars @ ..] if name == "processEnv" => {
+            let env = parse_env_vars(vars)
+                .map_err(|message| ReadGoModConfigError::Directive(directive.join(" "), message))?;
+            settings_for(&mut config.process_settings, process)
+                .env
+                .extend(env);
+        }
+        [name, process, dir] if name == "processDir" => {
+            settings_for(&mut config.process_settings, process).working_dir = Some(dir.clone());
+        }
+        [name, ..] if name == "processEnv" || name == "processDir" => {
+            return Err(ReadGoModConfigError::Directive(
+                directive.join(" "),
+                format!(
+                    "expected {name} {{name}} {}",
+                    if name == "processEnv" {
+                        "{key}={value}..."
+                    } else {
+                        "{path}"
+                    }
+                ),
+            ));
+        }
+        [name] if name == "registerProcfile" => config.register_procfile = true,
+        [name, path] if name == "vulnDB" => config.vuln.db = Some(PathBuf::from(path)),
+        [name, severity] if name == "vulnFailOn" => {
+            config.vuln.fail_on = Some(severity.parse().map_err(|message| {
+                ReadGoModConfigError::Directive(directive.join(" "), message)
+            })?);
+        }
+        [name] if name == "vulnScanGoSum" => config.vuln.scan_gosum = true,
+        [name, licenses @ ..] if name == "denyLicenses" => {
+            config.deny_licenses.extend(licenses.iter().cloned());
+        }
+        [name, pkg] if name == "webPackage" => config.web_package = Some(pkg.clone()),
+        [name] if name == "reproducible" => config.reproducible = true,
+        [name] if name == "verifyModules" => config.verify_modules = true,
+        [name] if name == "launchToolchain" => config.toolchain.launch = true,
+        [name, platforms @ ..] if name == "crossCompile" => {
+            for platform in platforms {
+                let platform = platform.parse().map_err(|message| {
+                    ReadGoModConfigError::Directive(directive.join(" "), message)
+                })?;
+                // Each platform is built into its own directory once.
+                if !config.cross_compile.contains(&platform) {
+                    config.cross_compile.push(platform);
+                }
+            }
+        }
+        [name, policy] if name == "buildCacheOnChange" => {
+            config.build_cache = policy
+                .parse()
+                .map_err(|message| ReadGoModConfigError::Directive(directive.join(" "), message))?;
+        }
+        [name, levels @ ..] if name == "microarch" => {
+            let levels = parse_microarch_levels(levels)
+                .map_err(|message| ReadGoModConfigError::Directive(directive.join(" "), message))?;
+            config.build_env.microarch.extend(levels);
+        }
+        [name, experiments @ ..] if name == "goExperiment" => {
+            config.build_env.experiments.extend(
+                experiments
+                    .iter()
+                    .flat_map(|experiments| experiments.split(','))
+                    .filter(|experiment| !experiment.is_empty())
+                    .map(ToString::to_string),
+            );
+        }
+        [name, count] if name == "toolchainCacheSize" => {
+            config.toolchain.cache_size = match count.parse() {
+                Ok(count) if count > 0 => count,
+                _ => {
+                    return Err(ReadGoModConfigError::Directive(
+                        directive.join(" "),
+                        String::from("expected a positive number of toolchains"),
+                    ));
+                }
+            };
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn parse_process_directive(args: &[String]) -> Result<ProcessConfig, String> {
+    let (name, args) = args
+        .split_first()
+        .ok_or("expected a process name and binary")?;
+    let mut default = false;
+    let mut working_dir = None;
+    let mut args = args.iter();
+    let binary = loop {
+        match args.next().map(String::as_str) {
+            Some("--default") => default = true,
+            Some(option) if option.starts_with("--dir=") => {
+                working_dir = option.strip_prefix("--dir=").map(ToString::to_string);
+            }
+            Some(option) if option.starts_with("--") => {
+                return Err(format!("unknown option {option}"));
+            }
+            Some(binary) => break binary.to_string(),
+            None => return Err(format!("expected a binary for process {name}")),
+        }
+    };
+    Ok(ProcessConfig {
+        name: name.clone(),
+        binary,
+        args: args.cloned().collect(),
+        working_dir,
+        default,
+    })
+}
+
+fn parse_env_vars(vars: &[String]) -> Result<Vec<(String, String)>, String> {
+    if vars.is_empty() {
+        return Err(String::from("expected at least one {key}={value}"));
+    }
+    vars.iter()
+        .map(|var| {
+            var.split_once('=')
+                .filter(|(key, _)| !key.is_empty())
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| format!("expected {{key}}={{value}}, found {var}"))
+        })
+        .collect()
+}
+
+fn parse_microarch_levels(levels: &[String]) -> Result<Vec<(String, String)>, String> {
+    if levels.is_empty() {
+        return Err(String::from("expected at least one {goarch}={level}"));
+    }
+    levels
+        .iter()
+        .map(|level| match level.split_once('=') {
+            Some((goarch @ ("amd64" | "arm64"), value)) if !value.is_empty() => {
+                Ok((goarch.to_string(), value.to_string()))
+            }
+            _ => Err(format!(
+                "expected amd64={{level}} or arm64={{level}}, found {level}"
+            )),
+        })
+        .collect()
+}
+
+fn settings_for<'a>(settings: &'a mut Vec<ProcessSettings>, name: &str) -> &'a mut ProcessSettings {
+    let index = settings
+        .iter()
+        .position(|settings| settings.name == name)
+        .unwrap_or_else(|| {
+            settings.push(ProcessSettings {
+                name: name.to_string(),
+                env: vec![],
+                working_dir: None,
+            });
+            settings.len() - 1
+        });
+    &mut settings[index]
+}
+
+/// A `//go:debug` directive found in a Go source file.
+pub(crate) struct GoDebugDirective {
+    pub(crate) path: PathBuf,
+    pub(crate) setting: String,
+}
+
+/// Find `//go:debug` directives in the non-test Go source files of a
+/// project. These directives must appear before the package clause, so only
+/// the file header is read, and invalid UTF-8 is replaced. The `vendor` and
+/// `testdata` directories, and hidden directories, are skipped. Directories
+/// and files that can't be read are skipped with a warning, since the go
+/// command reports any that matter for the build.
+pub(crate) fn find_go_debug_directives(app_dir: &Path) -> Vec<GoDebugDirective> {
+    let mut directives = vec![];
+    let mut dirs = vec![app_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match fs::read_dir(&dir).and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        {
+            Ok(entries) => entries,
+            Err(error) => {
+                warn_unreadable(&dir, &error);
+                continue;
+            }
+        };
+        entries.sort_by_key(fs::DirEntry::file_name);
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                if !(name.starts_with('.') || name == "vendor" || name == "testdata") {
+                    dirs.push(path);
+                }
+                continue;
             }
-            (Some("//"), Some("+heroku"), Some("goVersion"), Some(vrs)) => {
-                version = parse_go_version_requirement(vrs).map(Some)?;
+            if path.extension().is_none_or(|ext| ext != "go") || name.ends_with("_test.go") {
+                continue;
             }
-            (Some("go"), Some(vrs), None, None) if version.is_none() => {
-                version = parse_go_version_requirement(&format!("={vrs}")).map(Some)?;
+            match read_go_debug_settings(&path) {
+                Ok(settings) => {
+                    directives.extend(settings.into_iter().map(|setting| GoDebugDirective {
+                        path: path.strip_prefix(app_dir).unwrap_or(&path).to_path_buf(),
+                        setting,
+                    }));
+                }
+                Err(error) => warn_unreadable(&path, &error),
             }
-            _ => (),
         }
     }
-    Ok(GoModConfig { packages, version })
+    directives
+}
+
+/// Read the `//go:debug` settings of a Go source file, up to its package
+/// clause.
+fn read_go_debug_settings(path: &Path) -> std::io::Result<Vec<String>> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut settings = vec![];
+    let mut line = vec![];
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        if text.starts_with("package ") {
+            break;
+        }
+        if let Some(setting) = text.strip_prefix("//go:debug ") {
+            settings.push(setting.trim().to_string());
+        }
+        line.clear();
+    }
+    Ok(settings)
+}
+
+fn warn_unreadable(path: &Path, error: &std::io::Error) {
+    print::warning(format!(
+        "Couldn't read {} to find //go:debug directives: {error}",
+        path.display()
+    ));
+}
+
+/// Print and validate the `go.mod` and `//go:debug` settings that affect the
+/// build, given the Go version that will be used.
+pub(crate) fn print_gomod_settings(
+    config: &GoModConfig,
+    go_debug_directives: &[GoDebugDirective],
+    go_version: &GoVersion,
+) {
+    for warning in &config.gomod.warnings {
+        print::warning(format!("Skipping invalid go.mod statement: {warning}"));
+    }
+    if let Some(module) = &config.gomod.module {
+        print::sub_bullet(format!("Module: {}", style::value(module)));
+    }
+    if let Some(toolchain) = &config.gomod.toolchain {
+        print::sub_bullet(format!("Toolchain directive: {}", style::value(toolchain)));
+        if GoVersion::try_from(toolchain.clone()).is_ok_and(|toolchain| &toolchain > go_version) {
+            print::warning(format!(
+                "The go.mod toolchain directive requests {toolchain}, which is newer than the \
+                installed {go_version}. The go command may attempt to download it during the build."
+            ));
+        }
+    }
+    for (key, value) in &config.gomod.godebug {
+        print::sub_bullet(format!(
+            "go.mod godebug setting: {}",
+            style::value(format!("{key}={value}"))
+        ));
+    }
+    for directive in go_debug_directives {
+        if parse_godebug_setting(&directive.setting).is_some() {
+            print::sub_bullet(format!(
+                "//go:debug setting in {}: {}",
+                directive.path.display(),
+                style::value(&directive.setting)
+            ));
+        } else {
+            print::warning(format!(
+                "Invalid //go:debug directive in {}: {}. Expected key=value.",
+                directive.path.display(),
+                directive.setting
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn read_gomod_config_directives() {
+        let dir = tempfile::tempdir().unwrap();
+        let gomod = dir.path().join("go.mod");
+        fs::write(
+            &gomod,
+            "// +heroku goVersion ~1.22\n// +heroku install ./cmd/web ./cmd/worker\nmodule example.com/app\n\ngo 1.21\n\ngodebug (\n\tpanicnil=1\n)\n",
+        )
+        .unwrap();
+
+        let config = read_gomod_config(gomod).unwrap();
+        assert_eq!(config.version.unwrap().to_string(), "~1.22");
+        assert_eq!(config.packages.unwrap(), ["./cmd/web", "./cmd/worker"]);
+        assert!(!config.register_procfile);
+        assert!(!config.reproducible);
+        assert!(!config.verify_modules);
+        assert_eq!(config.toolchain, ToolchainConfig::default());
+        assert!(config.cross_compile.is_empty());
+        assert_eq!(config.build_cache, BuildCachePolicy::Clear);
+        assert_eq!(config.build_env, BuildEnvConfig::default());
+        assert!(config.web_package.is_none());
+        assert_eq!(
+            config.gomod.godebug,
+            [("panicnil".to_string(), "1".to_string())]
+        );
+    }
+
+    fn read_config(contents: &str) -> GoModConfig {
+        let dir = tempfile::tempdir().unwrap();
+        let gomod = dir.path().join("go.mod");
+        fs::write(&gomod, contents).unwrap();
+        read_gomod_config(gomod).unwrap()
+    }
+
+    #[test]
+    fn read_gomod_config_processes() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku process web --default --dir=public server -addr :8080
+            // +heroku process worker worker
+        "});
+        assert_eq!(
+            config.processes,
+            [
+                ProcessConfig {
+                    name: "web".to_string(),
+                    binary: "server".to_string(),
+                    args: vec!["-addr".to_string(), ":8080".to_string()],
+                    working_dir: Some("public".to_string()),
+                    default: true,
+                },
+                ProcessConfig {
+                    name: "worker".to_string(),
+                    binary: "worker".to_string(),
+                    args: vec![],
+                    working_dir: None,
+                    default: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_gomod_config_process_settings() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku processEnv worker GOMAXPROCS=1 QUEUE=a=b
+            // +heroku processDir worker jobs
+            // +heroku processEnv worker DEBUG=
+        "});
+        assert_eq!(
+            config.process_settings,
+            [ProcessSettings {
+                name: "worker".to_string(),
+                env: vec![
+                    ("GOMAXPROCS".to_string(), "1".to_string()),
+                    ("QUEUE".to_string(), "a=b".to_string()),
+                    ("DEBUG".to_string(), String::new()),
+                ],
+                working_dir: Some("jobs".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn read_gomod_config_web_package() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku webPackage example.com/app/cmd/server
+        "});
+        assert_eq!(
+            config.web_package.as_deref(),
+            Some("example.com/app/cmd/server")
+        );
+    }
+
+    #[test]
+    fn read_gomod_config_register_procfile() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku registerProcfile
+        "});
+        assert!(config.register_procfile);
+    }
+
+    #[test]
+    fn read_gomod_config_vuln() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku vulnDB vulndb
+            // +heroku vulnFailOn high
+            // +heroku vulnScanGoSum
+        "});
+        assert_eq!(
+            config.vuln,
+            VulnConfig {
+                db: Some(PathBuf::from("vulndb")),
+                fail_on: Some(Severity::High),
+                scan_gosum: true,
+            }
+        );
+    }
+
+    #[test]
+    fn read_gomod_config_licenses() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku denyLicenses AGPL GPL-3.0
+            // +heroku denyLicenses SSPL-1.0
+        "});
+        assert_eq!(config.deny_licenses, ["AGPL", "GPL-3.0", "SSPL-1.0"]);
+    }
+
+    #[test]
+    fn read_gomod_config_toolchain_cache_size() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku toolchainCacheSize 5
+        "});
+        assert_eq!(config.toolchain.cache_size, 5);
+        assert!(!config.toolchain.launch);
+    }
+
+    #[test]
+    fn read_gomod_config_launch_toolchain() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku launchToolchain
+        "});
+        assert!(config.toolchain.launch);
+        assert_eq!(config.toolchain.cache_size, DEFAULT_TOOLCHAIN_CACHE_SIZE);
+    }
+
+    #[test]
+    fn read_gomod_config_reproducible() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku reproducible
+        "});
+        assert!(config.reproducible);
+    }
+
+    #[test]
+    fn read_gomod_config_verify_modules() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku verifyModules
+        "});
+        assert!(config.verify_modules);
+    }
+
+    #[test]
+    fn read_gomod_config_build_cache() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku buildCacheOnChange keep
+        "});
+        assert_eq!(config.build_cache, BuildCachePolicy::Keep);
+    }
+
+    #[test]
+    fn read_gomod_config_build_env() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku microarch amd64=v3 arm64=v8.2,lse
+            // +heroku goExperiment rangefunc,noswissmap
+            // +heroku goExperiment arenas
+        "});
+        assert_eq!(
+            config.build_env,
+            BuildEnvConfig {
+                microarch: vec![
+                    ("amd64".to_string(), "v3".to_string()),
+                    ("arm64".to_string(), "v8.2,lse".to_string()),
+                ],
+                experiments: vec![
+                    "rangefunc".to_string(),
+                    "noswissmap".to_string(),
+                    "arenas".to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn read_gomod_config_cross_compile() {
+        let config = read_config(indoc! {"
+            module example.com/app
+
+            // +heroku crossCompile darwin/arm64 windows/amd64 darwin/arm64
+            // +heroku crossCompile windows/amd64 linux/arm64
+        "});
+        assert_eq!(
+            config
+                .cross_compile
+                .iter()
+                .map(|platform| (platform.to_string(), platform.dir_name()))
+                .collect::<Vec<_>>(),
+            [
+                ("darwin/arm64".to_string(), "darwin_arm64".to_string()),
+                ("windows/amd64".to_string(), "windows_amd64".to_string()),
+                ("linux/arm64".to_string(), "linux_arm64".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_gomod_config_invalid_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let gomod = dir.path().join("go.mod");
+        for (directive, expected) in [
+            (
+                "process web --default",
+                "Invalid go.mod directive `// +heroku process web --default`: expected a binary for process web",
+            ),
+            (
+                "process web --port=80 server",
+                "Invalid go.mod directive `// +heroku process web --port=80 server`: unknown option --port=80",
+            ),
+            (
+                "processEnv worker GOMAXPROCS",
+                "Invalid go.mod directive `// +heroku processEnv worker GOMAXPROCS`: expected {key}={value}, found GOMAXPROCS",
+            ),
+            (
+                "processEnv worker",
+                "Invalid go.mod directive `// +heroku processEnv worker`: expected at least one {key}={value}",
+            ),
+            (
+                "vulnFailOn severe",
+                "Invalid go.mod directive `// +heroku vulnFailOn severe`: unknown severity severe, expected one of: unknown, low, moderate, high, critical",
+            ),
+            (
+                "processDir worker",
+                "Invalid go.mod directive `// +heroku processDir worker`: expected processDir {name} {path}",
+            ),
+            (
+                "crossCompile darwin",
+                "Invalid go.mod directive `// +heroku crossCompile darwin`: expected {goos}/{goarch}, found darwin",
+            ),
+            (
+                "buildCacheOnChange reset",
+                "Invalid go.mod directive `// +heroku buildCacheOnChange reset`: expected clear or keep, found reset",
+            ),
+            (
+                "microarch 386=sse2",
+                "Invalid go.mod directive `// +heroku microarch 386=sse2`: expected amd64={level} or arm64={level}, found 386=sse2",
+            ),
+        ] {
+            fs::write(
+                &gomod,
+                format!("module example.com/app\n// +heroku {directive}\n"),
+            )
+            .unwrap();
+            let err = read_gomod_config(&gomod).err().unwrap();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn read_gomod_config_go_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let gomod = dir.path().join("go.mod");
+        fs::write(&gomod, "module example.com/app\n\ngo 1.21\n").unwrap();
+
+        let config = read_gomod_config(gomod).unwrap();
+        assert_eq!(config.version.unwrap().to_string(), "=1.21");
+        assert!(config.packages.is_none());
+    }
+
+    #[test]
+    fn find_go_debug_directives_in_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, contents) in [
+            (
+                "cmd/web/main.go",
+                "//go:build linux\n//go:debug panicnil=1\n\npackage main\n//go:debug ignored=1\n",
+            ),
+            ("main_test.go", "//go:debug test=1\npackage main\n"),
+            ("vendor/dep/dep.go", "//go:debug vendor=1\npackage dep\n"),
+            ("main.go", "//go:debug http2client=0\npackage main\n"),
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        // Non-UTF-8 content is read lossily.
+        fs::write(
+            dir.path().join("latin1.go"),
+            b"// caf\xe9\n//go:debug x509sha1=1\npackage main\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("binary.go"),
+            b"//go:debug zipinsecurepath=0\npackage main\n\xff\xfe",
+        )
+        .unwrap();
+        let directives = find_go_debug_directives(dir.path());
+        let found = directives
+            .iter()
+            .map(|d| (d.path.to_string_lossy().to_string(), d.setting.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("binary.go".to_string(), "zipinsecurepath=0"),
+                ("latin1.go".to_string(), "x509sha1=1"),
+                ("main.go".to_string(), "http2client=0"),
+                ("cmd/web/main.go".to_string(), "panicnil=1"),
+            ]
+        );
+    }
 }
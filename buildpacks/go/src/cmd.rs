@@ -1,24 +1,49 @@
+use crate::diag::{Diagnosis, diagnose};
 use bullet_stream::global::print;
 use fun_run::{CmdError, CommandWithName, NamedCommand};
 use libcnb::Env;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum Error {
     #[error("{0}")]
     Command(CmdError),
+    #[error("Couldn't parse command output: {0}")]
+    Json(serde_json::Error),
 }
 
-/// Run `go install -tags heroku pkg [..pkgn]`. Useful for compiling a list
-/// of packages and installing each of them in `GOBIN`. This command is module
-/// aware, and will download required modules as a side-effect.
+impl Error {
+    /// Known failure modes recognized in the output of the failed command.
+    pub(crate) fn diagnoses(&self) -> Vec<Diagnosis> {
+        match self {
+            Error::Command(cmd_err) => cmd_err
+                .output()
+                .map(|output| diagnose(&output.stderr_lossy()))
+                .unwrap_or_default(),
+            Error::Json(_) => vec![],
+        }
+    }
+}
+
+/// Run `go install -tags heroku [flags] pkg [..pkgn]`. Useful for compiling
+/// a list of packages and installing each of them in `GOBIN`. This command is
+/// module aware, and will download required modules as a side-effect.
 ///
 /// # Errors
 ///
 /// Returns an error if the command exit code is not 0 or if there is an IO
 /// issue with the command.
-pub(crate) fn go_install<S: AsRef<str>>(packages: &[S], go_env: &Env) -> Result<(), Error> {
+pub(crate) fn go_install<S: AsRef<str>>(
+    packages: &[S],
+    flags: &[String],
+    go_env: &Env,
+) -> Result<(), Error> {
     let mut args = vec!["install", "-tags", "heroku"];
+    args.extend(flags.iter().map(String::as_str));
     for pkg in packages {
         args.push(pkg.as_ref());
     }
@@ -27,6 +52,87 @@ pub(crate) fn go_install<S: AsRef<str>>(packages: &[S], go_env: &Env) -> Result<
     Ok(())
 }
 
+/// A module reported by `go mod download -json`.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct DownloadedModule {
+    pub(crate) path: String,
+    pub(crate) version: String,
+    pub(crate) zip: Option<PathBuf>,
+    pub(crate) error: Option<String>,
+}
+
+impl DownloadedModule {
+    /// Whether the module was downloaded, rather than found in the module
+    /// cache, given the module zips that were cached beforehand.
+    pub(crate) fn downloaded(&self, cached_zips: &BTreeSet<PathBuf>) -> bool {
+        self.zip
+            .as_ref()
+            .is_some_and(|zip| !cached_zips.contains(zip))
+    }
+}
+
+/// List the module zips in a module cache (`GOMODCACHE`). Useful for telling
+/// the modules `go mod download` downloads from those it finds in the cache.
+/// Directories that can't be read are skipped, so a missing module cache has
+/// no zips.
+pub(crate) fn cached_module_zips(mod_cache: &Path) -> BTreeSet<PathBuf> {
+    let mut zips = BTreeSet::new();
+    let mut dirs = vec![mod_cache.join("cache").join("download")];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "zip") {
+                zips.insert(path);
+            }
+        }
+    }
+    zips
+}
+
+/// Run `go mod download -json`. Useful for downloading the modules needed
+/// to build the main module into `GOMODCACHE`, and reporting each of them.
+///
+/// # Errors
+///
+/// Returns an error if the command exit code is not 0, if there is an IO
+/// issue with the command, or if the output can't be parsed.
+pub(crate) fn go_mod_download(go_env: &Env) -> Result<Vec<DownloadedModule>, Error> {
+    let output = print::sub_time_cmd(
+        Command::new("go")
+            .args(["mod", "download", "-json"])
+            .envs(go_env),
+    )
+    .map_err(Error::Command)?;
+    parse_mod_download(&output.stdout_lossy()).map_err(Error::Json)
+}
+
+/// Parse the stream of JSON objects written by `go mod download -json`.
+fn parse_mod_download(output: &str) -> Result<Vec<DownloadedModule>, serde_json::Error> {
+    serde_json::Deserializer::from_str(output)
+        .into_iter()
+        .collect()
+}
+
+/// Run `go mod verify`. Useful for checking that the modules in
+/// `GOMODCACHE` haven't been modified since they were downloaded, and match
+/// the hashes in `go.sum`.
+///
+/// # Errors
+///
+/// Returns an error if the command exit code is not 0 or if there is an IO
+/// issue with the command.
+pub(crate) fn go_mod_verify(go_env: &Env) -> Result<(), Error> {
+    print::sub_stream_cmd(Command::new("go").args(["mod", "verify"]).envs(go_env))
+        .map_err(Error::Command)?;
+    Ok(())
+}
+
 /// Run `go list -tags -f {{ .ImportPath }} ./...`. Useful for listing
 /// `main` packages in a go project to determine which packages to build.
 /// This command is module aware, and will download required modules as a
@@ -56,3 +162,246 @@ pub(crate) fn go_list(go_env: &Env) -> Result<Vec<String>, Error> {
         .map(|s| s.trim().to_string())
         .collect())
 }
+
+/// Packages listed by `go list -deps`, with their direct imports.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct PackageImports {
+    /// The import paths of the requested packages, in order.
+    pub(crate) packages: Vec<String>,
+    /// The direct imports of the requested packages, and of every
+    /// non-standard package they depend on.
+    pub(crate) imports: BTreeMap<String, Vec<String>>,
+}
+
+/// Run `go list -tags heroku -deps -f {{ .ImportPath }} {{ .Imports }} pkg [..pkgn]`.
+/// Useful for finding the packages each of a list of packages imports,
+/// directly or through other non-standard packages.
+///
+/// # Errors
+///
+/// Returns an error if the command exit code is not 0 or if there is an IO
+/// issue with the command.
+pub(crate) fn go_list_imports<S: AsRef<str>>(
+    packages: &[S],
+    go_env: &Env,
+) -> Result<PackageImports, Error> {
+    let mut command = std::process::Command::new("go");
+    let mut short: NamedCommand = command
+        .envs(go_env)
+        .args(["list", "-tags", "heroku", "-deps"])
+        .into();
+    // Hide these (possibly confusing) flags from build output. Requested
+    // packages are marked with `+`, and their dependencies with `-`.
+    short.mut_cmd().args([
+        "-f",
+        "{{ if not .Standard }}{{ if .DepOnly }}-{{ else }}+{{ end }}{{ .ImportPath }}{{ range .Imports }} {{ . }}{{ end }}{{ end }}",
+    ]);
+    short.mut_cmd().args(packages.iter().map(AsRef::as_ref));
+    let output = print::sub_time_cmd(short).map_err(Error::Command)?;
+    Ok(parse_package_imports(&output.stdout_lossy()))
+}
+
+fn parse_package_imports(output: &str) -> PackageImports {
+    let mut listed = PackageImports::default();
+    for line in output.lines() {
+        let mut fields = line.split_whitespace().map(ToString::to_string);
+        let Some(package) = fields.next() else {
+            continue;
+        };
+        let (requested, package) = match package.split_at_checked(1) {
+            Some(("+", package)) => (true, package.to_string()),
+            Some(("-", package)) => (false, package.to_string()),
+            _ => continue,
+        };
+        if requested {
+            listed.packages.push(package.clone());
+        }
+        listed.imports.insert(package, fields.collect());
+    }
+    listed
+}
+
+/// A package reported by `go list -deps -json`, with its source files.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase", default)]
+pub(crate) struct ListedPackage {
+    pub(crate) dir: PathBuf,
+    pub(crate) standard: bool,
+    go_files: Vec<String>,
+    cgo_files: Vec<String>,
+    c_files: Vec<String>,
+    #[serde(rename = "CXXFiles")]
+    cxx_files: Vec<String>,
+    m_files: Vec<String>,
+    h_files: Vec<String>,
+    f_files: Vec<String>,
+    s_files: Vec<String>,
+    swig_files: Vec<String>,
+    #[serde(rename = "SwigCXXFiles")]
+    swig_cxx_files: Vec<String>,
+    syso_files: Vec<String>,
+    embed_files: Vec<String>,
+}
+
+impl ListedPackage {
+    /// The paths of the files the package is compiled from, including
+    /// embedded files.
+    pub(crate) fn files(&self) -> impl Iterator<Item = PathBuf> {
+        [
+            &self.go_files,
+            &self.cgo_files,
+            &self.c_files,
+            &self.cxx_files,
+            &self.m_files,
+            &self.h_files,
+            &self.f_files,
+            &self.s_files,
+            &self.swig_files,
+            &self.swig_cxx_files,
+            &self.syso_files,
+            &self.embed_files,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|file| self.dir.join(file))
+    }
+}
+
+/// Run `go list -deps -json -tags heroku pkg [..pkgn]`. Useful for finding
+/// the source files that the packages, and every package they depend on,
+/// are built from.
+///
+/// # Errors
+///
+/// Returns an error if the command exit code is not 0, if there is an IO
+/// issue with the command, or if the output can't be parsed.
+pub(crate) fn go_list_deps<S: AsRef<str>>(
+    packages: &[S],
+    go_env: &Env,
+) -> Result<Vec<ListedPackage>, Error> {
+    let output = print::sub_time_cmd(
+        Command::new("go")
+            .args(["list", "-deps", "-json", "-tags", "heroku"])
+            .args(packages.iter().map(AsRef::as_ref))
+            .envs(go_env),
+    )
+    .map_err(Error::Command)?;
+    serde_json::Deserializer::from_str(&output.stdout_lossy())
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .map_err(Error::Json)
+}
+
+/// Run `go version -m dir`. Useful for reading the build information, such
+/// as the modules and build settings, embedded in each Go binary in a
+/// directory.
+///
+/// # Errors
+///
+/// Returns an error if the command exit code is not 0 or if there is an IO
+/// issue with the command.
+pub(crate) fn go_version_m(dir: &Path, go_env: &Env) -> Result<String, Error> {
+    let output = print::sub_time_cmd(
+        Command::new("go")
+            .args(["version", "-m"])
+            .arg(dir)
+            .envs(go_env),
+    )
+    .map_err(Error::Command)?;
+    Ok(output.stdout_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mod_download_output() {
+        let mod_cache = tempfile::tempdir().unwrap();
+        let download_dir = mod_cache
+            .path()
+            .join("cache/download/github.com/gin-gonic/gin/@v");
+        fs::create_dir_all(&download_dir).unwrap();
+        let zip = download_dir.join("v1.9.1.zip");
+        let output = format!(
+            "{{\n\t\"Path\": \"github.com/gin-gonic/gin\",\n\t\"Version\": \"v1.9.1\",\n\t\"Zip\": {:?},\n\t\"Sum\": \"h1:4idEAncQnU5cB7BeOkPtxjfCSye0AAm1R0RVIqJ+Jmg=\"\n}}\n\
+            {{\n\t\"Path\": \"example.com/missing\",\n\t\"Version\": \"v1.0.0\",\n\t\"Error\": \"not found\"\n}}\n",
+            zip.to_string_lossy()
+        );
+
+        let modules = parse_mod_download(&output).unwrap();
+        assert_eq!(
+            modules,
+            [
+                DownloadedModule {
+                    path: "github.com/gin-gonic/gin".to_string(),
+                    version: "v1.9.1".to_string(),
+                    zip: Some(zip.clone()),
+                    error: None,
+                },
+                DownloadedModule {
+                    path: "example.com/missing".to_string(),
+                    version: "v1.0.0".to_string(),
+                    zip: None,
+                    error: Some("not found".to_string()),
+                },
+            ]
+        );
+        assert!(modules[0].downloaded(&cached_module_zips(mod_cache.path())));
+        assert!(!modules[1].downloaded(&cached_module_zips(mod_cache.path())));
+
+        fs::write(&zip, "zip").unwrap();
+        fs::write(download_dir.join("v1.9.1.mod"), "module").unwrap();
+        let cached_zips = cached_module_zips(mod_cache.path());
+        assert_eq!(cached_zips, BTreeSet::from([zip]));
+        assert!(!modules[0].downloaded(&cached_zips));
+    }
+
+    #[test]
+    fn parse_go_list_imports_output() {
+        let listed = parse_package_imports(
+            "\n-example.com/app/internal/server net/http\n\n+example.com/app/cmd/app example.com/app/internal/server fmt\n+example.com/app/cmd/worker\n",
+        );
+        assert_eq!(
+            listed.packages,
+            ["example.com/app/cmd/app", "example.com/app/cmd/worker"]
+        );
+        assert_eq!(
+            listed.imports["example.com/app/cmd/app"],
+            ["example.com/app/internal/server", "fmt"]
+        );
+        assert_eq!(
+            listed.imports["example.com/app/internal/server"],
+            ["net/http"]
+        );
+        assert!(listed.imports["example.com/app/cmd/worker"].is_empty());
+    }
+
+    #[test]
+    fn listed_package_files() {
+        let package: ListedPackage = serde_json::from_str(
+            r#"{
+                "Dir": "/workspace/cmd/web",
+                "ImportPath": "example.com/app/cmd/web",
+                "GoFiles": ["main.go"],
+                "CgoFiles": ["cgo.go"],
+                "CXXFiles": ["lib.cc"],
+                "HFiles": ["lib.h"],
+                "EmbedFiles": ["static/index.html"]
+            }"#,
+        )
+        .unwrap();
+        assert!(!package.standard);
+        assert_eq!(
+            package.files().collect::<Vec<_>>(),
+            [
+                "/workspace/cmd/web/main.go",
+                "/workspace/cmd/web/cgo.go",
+                "/workspace/cmd/web/lib.cc",
+                "/workspace/cmd/web/lib.h",
+                "/workspace/cmd/web/static/index.html",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use bullet_stream::style;
+use indoc::formatdoc;
+
+/// A frequent `go` command failure mode recognized from its output.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Diagnosis {
+    MissingGoSum,
+    GoVersionTooOld { required: String, running: String },
+    MissingModule { package: String },
+    PrivateRepoAuth,
+    InconsistentVendoring,
+    OutOfDisk,
+}
+
+/// Classifies `go` command output (usually stderr) into zero or more known
+/// failure modes. The order of the result is stable, and each failure mode
+/// appears at most once.
+pub(crate) fn diagnose(output: &str) -> Vec<Diagnosis> {
+    let mut diagnoses = vec![];
+    if output.contains("missing go.sum entry") {
+        diagnoses.push(Diagnosis::MissingGoSum);
+    }
+    if let Some(line) = output.lines().find(|l| l.contains("requires go >= ")) {
+        diagnoses.push(Diagnosis::GoVersionTooOld {
+            required: token_after(line, "requires go >= ").unwrap_or_default(),
+            running: token_after(line, "(running go ").unwrap_or_default(),
+        });
+    }
+    if let Some(package) = output.lines().find_map(|l| {
+        token_after(l, "cannot find module providing package ")
+            .or_else(|| token_after(l, "no required module provides package "))
+    }) {
+        diagnoses.push(Diagnosis::MissingModule { package });
+    }
+    if [
+        "401 Unauthorized",
+        "403 Forbidden",
+        "could not read Username",
+        "terminal prompts disabled",
+        "Permission denied (publickey)",
+    ]
+    .iter()
+    .any(|needle| output.contains(needle))
+    {
+        diagnoses.push(Diagnosis::PrivateRepoAuth);
+    }
+    if output.contains("inconsistent vendoring") {
+        diagnoses.push(Diagnosis::InconsistentVendoring);
+    }
+    if output.contains("no space left on device") {
+        diagnoses.push(Diagnosis::OutOfDisk);
+    }
+    diagnoses
+}
+
+impl Diagnosis {
+    /// Remediation guidance for this failure mode, suitable for display
+    /// alongside the raw command output.
+    pub(crate) fn guidance(&self) -> String {
+        match self {
+            Diagnosis::MissingGoSum => formatdoc! {"
+                The `go.sum` file is missing entries for one or more modules. Run
+                {} locally and commit the updated `go.mod` and `go.sum` files.
+            ", style::command("go mod tidy")},
+            Diagnosis::GoVersionTooOld { required, running } => formatdoc! {"
+                A module requires go >= {required}, but the build is using go {running}.
+                Update the `go` line in `go.mod`, or select a newer version with the
+                `// +heroku goVersion` directive.
+            "},
+            Diagnosis::MissingModule { package } => formatdoc! {"
+                No module provides the package {package}. Check the import path for
+                typos, then run {} locally and commit the updated `go.mod` and
+                `go.sum` files.
+            ", style::command("go mod tidy")},
+            Diagnosis::PrivateRepoAuth => formatdoc! {"
+                A module download was denied, which usually means it is hosted in a
+                private repository. Set `GOPRIVATE` to the private module path prefix
+                and provide credentials (for example via a `.netrc` file), or vendor
+                the dependencies with {}.
+            ", style::command("go mod vendor")},
+            Diagnosis::InconsistentVendoring => formatdoc! {"
+                The `vendor` directory is out of sync with `go.mod`. Run {} locally
+                and commit the updated `vendor` directory.
+            ", style::command("go mod vendor")},
+            Diagnosis::OutOfDisk => formatdoc! {"
+                The build ran out of disk space. Reduce the size of the build (for
+                example by removing unused dependencies or large files from the
+                project), or clear the build cache and try again.
+            "},
+        }
+    }
+}
+
+/// Returns the whitespace-delimited token following `marker` in `line`,
+/// without trailing punctuation.
+fn token_after(line: &str, marker: &str) -> Option<String> {
+    line.split_once(marker)
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .map(|token| {
+            token
+                .trim_end_matches([';', ')', ':', ','])
+                .trim_matches('"')
+                .to_string()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnose_missing_go_sum() {
+        let stderr = "main.go:4:2: missing go.sum entry for module providing package github.com/gin-gonic/gin (imported by example.com/app); to add:\n\tgo get example.com/app\n";
+        assert_eq!(diagnose(stderr), [Diagnosis::MissingGoSum]);
+    }
+
+    #[test]
+    fn diagnose_go_version_too_old() {
+        let stderr = "go: go.mod requires go >= 1.23.0 (running go 1.22.5; GOTOOLCHAIN=local)\n";
+        assert_eq!(
+            diagnose(stderr),
+            [Diagnosis::GoVersionTooOld {
+                required: "1.23.0".to_string(),
+                running: "1.22.5".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn diagnose_missing_module() {
+        let stderr = "main.go:5:2: no required module provides package github.com/foo/bar; to add it:\n\tgo get github.com/foo/bar\ncmd/web/main.go:6:2: cannot find module providing package github.com/foo/baz: import lookup disabled by -mod=vendor\n";
+        assert_eq!(
+            diagnose(stderr),
+            [Diagnosis::MissingModule {
+                package: "github.com/foo/bar".to_string()
+            }]
+        );
+        let stderr = "cmd/web/main.go:6:2: cannot find module providing package github.com/foo/baz: import lookup disabled by -mod=vendor\n";
+        assert_eq!(
+            diagnose(stderr),
+            [Diagnosis::MissingModule {
+                package: "github.com/foo/baz".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn diagnose_private_repo_auth() {
+        for stderr in [
+            "go: github.com/acme/private@v1.2.0: reading https://proxy.golang.org/github.com/acme/private/@v/v1.2.0.mod: 403 Forbidden\n",
+            "go: github.com/acme/private@v1.2.0: invalid version: git ls-remote -q origin in /layers/heroku_go/go_deps/cache/cache/vcs/abc: exit status 128:\n\tfatal: could not read Username for 'https://github.com': terminal prompts disabled\n",
+            "go: gitlab.com/acme/private@v0.1.0: reading https://gitlab.com/api/v4/projects/acme%2Fprivate: 401 Unauthorized\n",
+        ] {
+            assert_eq!(diagnose(stderr), [Diagnosis::PrivateRepoAuth], "{stderr}");
+        }
+    }
+
+    #[test]
+    fn diagnose_inconsistent_vendoring() {
+        let stderr = "go: inconsistent vendoring in /workspace:\n\tgithub.com/gorilla/mux@v1.8.0: is explicitly required in go.mod, but not marked as explicit in vendor/modules.txt\n\n\tTo ignore the vendor directory, use -mod=readonly or -mod=mod.\n\tTo sync the vendor directory, run:\n\t\tgo mod vendor\n";
+        assert_eq!(diagnose(stderr), [Diagnosis::InconsistentVendoring]);
+    }
+
+    #[test]
+    fn diagnose_out_of_disk() {
+        let stderr = "go: error obtaining buildID for go tool compile: write /layers/heroku_go/go_build/cache/00/abc-d: no space left on device\n";
+        assert_eq!(diagnose(stderr), [Diagnosis::OutOfDisk]);
+    }
+
+    #[test]
+    fn diagnose_multiple() {
+        let stderr = "go: downloading github.com/acme/private v1.0.0\nverifying github.com/acme/private@v1.0.0: missing go.sum entry\nwrite /tmp/x: no space left on device\n";
+        assert_eq!(
+            diagnose(stderr),
+            [Diagnosis::MissingGoSum, Diagnosis::OutOfDisk]
+        );
+    }
+
+    #[test]
+    fn diagnose_unknown() {
+        assert!(
+            diagnose(
+                "main.go:3:1: syntax error: non-declaration statement outside function body\n"
+            )
+            .is_empty()
+        );
+    }
+}
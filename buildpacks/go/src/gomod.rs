@@ -0,0 +1,407 @@
+/// The contents of a `go.mod` file. Directives may be written on a single
+/// line or as a parenthesized block. Unknown or invalid statements are
+/// skipped with a warning, since the go command, not this buildpack, decides
+/// which `go.mod` files are valid, and newer Go versions may add directives.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct GoMod {
+    pub(crate) module: Option<String>,
+    pub(crate) go: Option<String>,
+    pub(crate) toolchain: Option<String>,
+    pub(crate) godebug: Vec<(String, String)>,
+    pub(crate) require: Vec<Require>,
+    pub(crate) replace: Vec<Replace>,
+    pub(crate) exclude: Vec<ModuleVersion>,
+    pub(crate) retract: Vec<String>,
+    pub(crate) tool: Vec<String>,
+    pub(crate) ignore: Vec<String>,
+    /// Arguments of `// +heroku` comment directives, in order of appearance.
+    pub(crate) heroku_directives: Vec<Vec<String>>,
+    /// Statements that were skipped because they're unknown or invalid.
+    pub(crate) warnings: Vec<GoModWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Require {
+    pub(crate) path: String,
+    pub(crate) version: String,
+    pub(crate) indirect: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModuleVersion {
+    pub(crate) path: String,
+    pub(crate) version: String,
+}
+
+/// A module path with an optional version, as used on either side of a
+/// `replace` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModulePath {
+    pub(crate) path: String,
+    pub(crate) version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Replace {
+    pub(crate) old: ModulePath,
+    pub(crate) new: ModulePath,
+}
+
+/// A `go.mod` statement that was skipped while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GoModWarning {
+    line: usize,
+    message: String,
+}
+
+impl std::fmt::Display for GoModWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "go.mod:{}: {}", self.line, self.message)
+    }
+}
+
+impl GoMod {
+    /// Parse the contents of a `go.mod` file, recording skipped statements
+    /// in `warnings`.
+    pub(crate) fn parse(contents: &str) -> Self {
+        let mut gomod = GoMod::default();
+        let mut block: Option<(usize, String)> = None;
+
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line = index + 1;
+            let (code, comment) = split_comment(raw_line);
+            let tokens = match tokenize(code) {
+                Ok(tokens) => tokens,
+                Err(message) => {
+                    gomod.warnings.push(GoModWarning { line, message });
+                    continue;
+                }
+            };
+
+            if tokens.is_empty() {
+                let mut parts = comment.unwrap_or_default().split_whitespace();
+                if parts.next() == Some("+heroku") {
+                    gomod
+                        .heroku_directives
+                        .push(parts.map(ToString::to_string).collect());
+                }
+                continue;
+            }
+
+            let (verb, args) = match (&block, tokens.as_slice()) {
+                (Some(_), [close]) if close == ")" => {
+                    block = None;
+                    continue;
+                }
+                (None, [verb, open]) if open == "(" => {
+                    block = Some((line, verb.clone()));
+                    continue;
+                }
+                (Some((_, verb)), args) | (None, [verb, args @ ..]) => (verb.as_str(), args),
+                (None, []) => continue,
+            };
+            if let Err(message) = gomod.apply(verb, args, comment) {
+                gomod.warnings.push(GoModWarning { line, message });
+            }
+        }
+
+        if let Some((line, verb)) = block {
+            gomod.warnings.push(GoModWarning {
+                line,
+                message: format!("unterminated {verb} block"),
+            });
+        }
+        gomod
+    }
+
+    fn apply(&mut self, verb: &str, args: &[String], comment: Option<&str>) -> Result<(), String> {
+        let usage = |usage: &str| format!("usage: {verb} {usage}");
+        match (verb, args) {
+            ("module", [path]) => self.module = Some(path.clone()),
+            ("module", _) => return Err(usage("module/path")),
+            ("go", [version]) => self.go = Some(version.clone()),
+            ("go", _) => return Err(usage("1.23")),
+            ("toolchain", [name]) => self.toolchain = Some(name.clone()),
+            ("toolchain", _) => return Err(usage("go1.23.4")),
+            ("godebug", [setting]) => self.godebug.push(
+                parse_godebug_setting(setting)
+                    .ok_or_else(|| format!("invalid godebug setting {setting:?}"))?,
+            ),
+            ("godebug", _) => return Err(usage("key=value")),
+            ("require", [path, version]) => self.require.push(Require {
+                path: path.clone(),
+                version: version.clone(),
+                indirect: comment.is_some_and(|comment| {
+                    let comment = comment.trim();
+                    comment == "indirect" || comment.starts_with("indirect;")
+                }),
+            }),
+            ("exclude", [path, version]) => self.exclude.push(ModuleVersion {
+                path: path.clone(),
+                version: version.clone(),
+            }),
+            ("require" | "exclude", _) => return Err(usage("module/path v1.2.3")),
+            ("replace", _) => {
+                let usage = usage(
+                    "module/path [v1.2.3] => other/module v1.4\n\t or replace module/path [v1.2.3] => ../local/directory",
+                );
+                let arrow = args.iter().position(|arg| arg == "=>").ok_or(&usage)?;
+                let module_path = |parts: &[String]| match parts {
+                    [path] => Some(ModulePath {
+                        path: path.clone(),
+                        version: None,
+                    }),
+                    [path, version] => Some(ModulePath {
+                        path: path.clone(),
+                        version: Some(version.clone()),
+                    }),
+                    _ => None,
+                };
+                self.replace.push(Replace {
+                    old: module_path(&args[..arrow]).ok_or(&usage)?,
+                    new: module_path(&args[arrow + 1..]).ok_or(&usage)?,
+                });
+            }
+            ("retract", []) => return Err(usage("version or [low, high]")),
+            ("retract", _) => self.retract.push(args.join(" ")),
+            ("tool", [path]) => self.tool.push(path.clone()),
+            ("tool", _) => return Err(usage("module/path/to/tool")),
+            ("ignore", [path]) => self.ignore.push(path.clone()),
+            ("ignore", _) => return Err(usage("./path/to/ignore")),
+            _ => return Err(format!("unknown directive: {verb}")),
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `key=value` GODEBUG setting.
+pub(crate) fn parse_godebug_setting(setting: &str) -> Option<(String, String)> {
+    setting
+        .split_once('=')
+        .filter(|(key, value)| {
+            !key.is_empty()
+                && !key.contains(|c: char| c.is_whitespace() || c == ',')
+                && !value.contains(|c: char| c.is_whitespace() || c == ',')
+        })
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+}
+
+/// Splits a line into code and an optional `//` comment, ignoring `//`
+/// within quoted strings.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '`') => quote = Some(c),
+            (None, '/') if line[index..].starts_with("//") => {
+                return (&line[..index], Some(&line[index + 2..]));
+            }
+            _ => (),
+        }
+        escaped = false;
+    }
+    (line, None)
+}
+
+/// Splits code into whitespace separated tokens, unquoting quoted strings.
+/// Parentheses are tokens of their own, as in `require(`.
+fn tokenize(code: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut chars = code.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(c.to_string());
+        } else if c == '"' || c == '`' {
+            chars.next();
+            let mut token = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') if c == '"' => token.extend(chars.next()),
+                    Some(next) if next == c => break,
+                    Some(next) => token.push(next),
+                    None => return Err(format!("unterminated quoted string: {code}")),
+                }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(&next) = chars.peek() {
+                if next.is_whitespace() || next == '(' || next == ')' {
+                    break;
+                }
+                token.push(next);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_gomod() {
+        let gomod = indoc! {r#"
+            // +heroku goVersion 1.22
+            module "example.com/app/v2" // the app
+
+            go 1.22.1
+            toolchain go1.23.4
+
+            godebug default=go1.21
+            godebug (
+                panicnil=1
+                http2client=0 // comment
+            )
+
+            require github.com/gin-gonic/gin v1.9.1
+            require(
+                github.com/pkg/errors v0.9.1 // indirect
+                golang.org/x/text v0.14.0
+            )
+
+            replace github.com/pkg/errors => ../errors
+            replace(
+                golang.org/x/text v0.14.0 => golang.org/x/text v0.15.0
+            )
+
+            exclude golang.org/x/net v1.2.3
+
+            retract (
+                v2.0.1 // published accidentally
+                [v2.0.2, v2.0.5]
+            )
+
+            tool golang.org/x/tools/cmd/stringer
+            ignore ./node_modules
+        "#};
+        let gomod = GoMod::parse(gomod);
+
+        assert_eq!(gomod.module.as_deref(), Some("example.com/app/v2"));
+        assert_eq!(gomod.go.as_deref(), Some("1.22.1"));
+        assert_eq!(gomod.toolchain.as_deref(), Some("go1.23.4"));
+        assert_eq!(
+            gomod.godebug,
+            [
+                ("default".to_string(), "go1.21".to_string()),
+                ("panicnil".to_string(), "1".to_string()),
+                ("http2client".to_string(), "0".to_string()),
+            ]
+        );
+        assert_eq!(
+            gomod.require,
+            [
+                Require {
+                    path: "github.com/gin-gonic/gin".to_string(),
+                    version: "v1.9.1".to_string(),
+                    indirect: false,
+                },
+                Require {
+                    path: "github.com/pkg/errors".to_string(),
+                    version: "v0.9.1".to_string(),
+                    indirect: true,
+                },
+                Require {
+                    path: "golang.org/x/text".to_string(),
+                    version: "v0.14.0".to_string(),
+                    indirect: false,
+                },
+            ]
+        );
+        assert_eq!(
+            gomod.replace,
+            [
+                Replace {
+                    old: ModulePath {
+                        path: "github.com/pkg/errors".to_string(),
+                        version: None,
+                    },
+                    new: ModulePath {
+                        path: "../errors".to_string(),
+                        version: None,
+                    },
+                },
+                Replace {
+                    old: ModulePath {
+                        path: "golang.org/x/text".to_string(),
+                        version: Some("v0.14.0".to_string()),
+                    },
+                    new: ModulePath {
+                        path: "golang.org/x/text".to_string(),
+                        version: Some("v0.15.0".to_string()),
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            gomod.exclude,
+            [ModuleVersion {
+                path: "golang.org/x/net".to_string(),
+                version: "v1.2.3".to_string(),
+            }]
+        );
+        assert_eq!(gomod.retract, ["v2.0.1", "[v2.0.2, v2.0.5]"]);
+        assert_eq!(gomod.tool, ["golang.org/x/tools/cmd/stringer"]);
+        assert_eq!(gomod.ignore, ["./node_modules"]);
+        assert_eq!(gomod.heroku_directives, [["goVersion", "1.22"]]);
+        assert!(gomod.warnings.is_empty(), "{:?}", gomod.warnings);
+    }
+
+    #[test]
+    fn parse_gomod_warnings() {
+        for (gomod, expected) in [
+            (
+                "module example.com/app\nrequire (\n",
+                "go.mod:2: unterminated require block",
+            ),
+            (
+                "module example.com/app\ngodebug panicnil\n",
+                "go.mod:2: invalid godebug setting \"panicnil\"",
+            ),
+            (
+                "module example.com/app\nrequire example.com/dep\n",
+                "go.mod:2: usage: require module/path v1.2.3",
+            ),
+            (
+                "module \"example.com/app\n",
+                "go.mod:1: unterminated quoted string: module \"example.com/app",
+            ),
+            (
+                "module example.com/app\nunknown directive\n",
+                "go.mod:2: unknown directive: unknown",
+            ),
+        ] {
+            assert_eq!(
+                GoMod::parse(gomod)
+                    .warnings
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                [expected],
+                "{gomod}"
+            );
+        }
+
+        // Valid statements are still read.
+        let gomod = GoMod::parse(
+            "module example.com/app\ngo 1.22 1.23\nrequire (\n\texample.com/dep v1.0.0\n",
+        );
+        assert_eq!(gomod.module.as_deref(), Some("example.com/app"));
+        assert_eq!(gomod.go, None);
+        assert_eq!(gomod.require.len(), 1);
+        assert_eq!(gomod.warnings.len(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+use heroku_go_utils::vrs::GoVersion;
+use libherokubuildpack::inventory::Inventory;
+use libherokubuildpack::inventory::artifact::{Arch, Artifact, Os};
+use semver::Op;
+use sha2::Sha256;
+use std::fmt::Display;
+
+pub(crate) type GoInventory = Inventory<GoVersion, Sha256, Option<()>>;
+pub(crate) type GoArtifact = Artifact<GoVersion, Sha256, Option<()>>;
+
+// The oldest Go release supported by this buildpack. Older releases predate
+// Go modules and are not included in the inventory.
+const MIN_SUPPORTED_VERSION: &str = "1.11";
+
+/// Alternatives to suggest when a Go version requirement can't be resolved
+/// from the inventory.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct VersionSuggestions {
+    newest: Option<GoVersion>,
+    newest_same_minor: Option<GoVersion>,
+    newer_than_inventory: bool,
+    unsupported: bool,
+}
+
+/// Finds the closest versions available in the inventory for the given os
+/// and arch to a requirement that couldn't be resolved.
+pub(crate) fn suggest_versions(
+    inventory: &GoInventory,
+    os: Option<Os>,
+    arch: Option<Arch>,
+    requirement: &semver::VersionReq,
+) -> VersionSuggestions {
+    let available = inventory
+        .artifacts
+        .iter()
+        .filter(|artifact| Some(artifact.os) == os && Some(artifact.arch) == arch)
+        .map(|artifact| &artifact.version);
+
+    let newest = available.clone().max().cloned();
+    // Every comparator bounds the requested versions from below, from above,
+    // or both, like `=1.22` or `~1.22`.
+    let bounds = requirement
+        .comparators
+        .iter()
+        .filter_map(|comparator| {
+            GoVersion::try_from(format!(
+                "{}.{}.{}",
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0)
+            ))
+            .ok()
+            .map(|version| (comparator.op, version))
+        })
+        .collect::<Vec<_>>();
+    let is_lower = |op: &Op| !matches!(op, Op::Less | Op::LessEq);
+    let is_upper = |op: &Op| !matches!(op, Op::Greater | Op::GreaterEq | Op::Caret);
+    let lower = bounds
+        .iter()
+        .filter(|(op, _)| is_lower(op))
+        .map(|(_, version)| version)
+        .max();
+    let upper = bounds
+        .iter()
+        .filter(|(op, _)| is_upper(op))
+        .map(|(_, version)| version)
+        .min();
+    let Some(requested) = lower.or(upper) else {
+        return VersionSuggestions {
+            newest,
+            ..VersionSuggestions::default()
+        };
+    };
+
+    let newest_same_minor = available
+        .filter(|version| version.major_release_version() == requested.major_release_version())
+        .max()
+        .cloned();
+    let newer_than_inventory = newest.as_ref().is_some_and(|newest| {
+        bounds.iter().any(|(op, version)| match op {
+            Op::Greater => version >= newest,
+            op => is_lower(op) && version > newest,
+        })
+    });
+    let unsupported =
+        GoVersion::try_from(MIN_SUPPORTED_VERSION.to_string()).is_ok_and(|min_version| {
+            bounds.iter().any(|(op, version)| match op {
+                Op::Less => version <= &min_version,
+                op => is_upper(op) && version < &min_version,
+            })
+        });
+
+    VersionSuggestions {
+        newest,
+        newest_same_minor,
+        newer_than_inventory,
+        unsupported,
+    }
+}
+
+impl Display for VersionSuggestions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.newer_than_inventory {
+            write!(
+                f,
+                "\n\nThe requested version is newer than any version known to this buildpack. \
+                The buildpack may be out of date."
+            )?;
+        }
+        if self.unsupported {
+            write!(
+                f,
+                "\n\nGo versions older than {MIN_SUPPORTED_VERSION} are not supported."
+            )?;
+        }
+        if let Some(version) = &self.newest_same_minor {
+            write!(
+                f,
+                "\n\nNewest available version in the same release: {version}"
+            )?;
+        }
+        if let Some(version) = &self.newest {
+            write!(f, "\n\nNewest available version: {version}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heroku_go_utils::vrs::parse_go_version_requirement;
+
+    const INVENTORY: &str = r#"
+[[artifacts]]
+version = "go1.22.5"
+os = "linux"
+arch = "amd64"
+url = "https://go.dev/dl/go1.22.5.linux-amd64.tar.gz"
+checksum = "sha256:904b924d435eaea086515bc63235b192ea441bd8c9b198c507e85009e6e4c7f0"
+
+[[artifacts]]
+version = "go1.23.1"
+os = "linux"
+arch = "amd64"
+url = "https://go.dev/dl/go1.23.1.linux-amd64.tar.gz"
+checksum = "sha256:49bbb517cfa9eee677e1e7897f7cf9cfdbcf49e05f61984a2789136de359f9bd"
+
+[[artifacts]]
+version = "go1.23.2"
+os = "linux"
+arch = "arm64"
+url = "https://go.dev/dl/go1.23.2.linux-arm64.tar.gz"
+checksum = "sha256:f626cdd92fc21a88b31c1251f419c17782933a42903db87a174ce74eeecc66a9"
+"#;
+
+    fn suggest(requirement: &str) -> VersionSuggestions {
+        suggest_versions(
+            &INVENTORY.parse().unwrap(),
+            Some(Os::Linux),
+            Some(Arch::Amd64),
+            &parse_go_version_requirement(requirement).unwrap(),
+        )
+    }
+
+    fn version(version: &str) -> GoVersion {
+        GoVersion::try_from(version.to_string()).unwrap()
+    }
+
+    #[test]
+    fn suggest_versions_same_minor() {
+        assert_eq!(
+            suggest("=1.22.99"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: Some(version("go1.22.5")),
+                newer_than_inventory: false,
+                unsupported: false,
+            }
+        );
+    }
+
+    #[test]
+    fn suggest_versions_newer_than_inventory() {
+        assert_eq!(
+            suggest("=1.30"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: None,
+                newer_than_inventory: true,
+                unsupported: false,
+            }
+        );
+    }
+
+    #[test]
+    fn suggest_versions_unsupported() {
+        assert_eq!(
+            suggest("=1.9"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: None,
+                newer_than_inventory: false,
+                unsupported: true,
+            }
+        );
+    }
+
+    #[test]
+    fn suggest_versions_ranges() {
+        assert_eq!(
+            suggest(">=1.22.99, <1.23"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: Some(version("go1.22.5")),
+                newer_than_inventory: false,
+                unsupported: false,
+            }
+        );
+        assert_eq!(
+            suggest("<1.40, >=1.30"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: None,
+                newer_than_inventory: true,
+                unsupported: false,
+            }
+        );
+        assert_eq!(
+            suggest(">=1.9, <1.11"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: None,
+                newer_than_inventory: false,
+                unsupported: true,
+            }
+        );
+        assert_eq!(
+            suggest(">=1.9, <1.50"),
+            VersionSuggestions {
+                newest: Some(version("go1.23.1")),
+                newest_same_minor: None,
+                newer_than_inventory: false,
+                unsupported: false,
+            }
+        );
+    }
+
+    #[test]
+    fn suggest_versions_display() {
+        assert_eq!(
+            suggest("=1.30").to_string(),
+            "\n\nThe requested version is newer than any version known to this buildpack. \
+            The buildpack may be out of date.\n\nNewest available version: go1.23.1"
+        );
+    }
+}
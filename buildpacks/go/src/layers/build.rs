@@ -1,39 +1,141 @@
+use crate::cfg::BuildCachePolicy;
 use crate::{GoBuildpack, GoBuildpackError};
 use bullet_stream::global::print;
 use heroku_go_utils::vrs::GoVersion;
-use libcnb::Target;
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
     CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
 };
 use libcnb::layer_env::{LayerEnv, Scope};
+use libcnb::{Env, Target};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
 
 const CACHE_ENV: &str = "GOCACHE";
 const CACHE_DIR: &str = "cache";
 const MAX_CACHE_USAGE_COUNT: f32 = 200.0;
 
+/// Environment variables that change the output of `go install`, and so the
+/// contents of the build cache.
+const BUILD_ENV_KEYS: [&str; 18] = [
+    "CC",
+    "CGO_CFLAGS",
+    "CGO_CPPFLAGS",
+    "CGO_CXXFLAGS",
+    "CGO_ENABLED",
+    "CGO_LDFLAGS",
+    "CXX",
+    "GO386",
+    "GOAMD64",
+    "GOARCH",
+    "GOARM",
+    "GOARM64",
+    "GOEXPERIMENT",
+    "GOFLAGS",
+    "GOOS",
+    "GOPPC64",
+    "GORISCV64",
+    "GOWASM",
+];
+
 #[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub(crate) struct BuildLayerMetadata {
     go_major_version: GoVersion,
     target_arch: String,
     target_distro_name: String,
     target_distro_version: String,
+    #[serde(default)]
+    build_settings: BTreeMap<String, String>,
+    #[serde(default)]
+    build_settings_hash: String,
     cache_usage_count: f32,
 }
 
 impl BuildLayerMetadata {
-    fn new(version: &GoVersion, target: &Target) -> Self {
+    fn new(version: &GoVersion, target: &Target, build_settings: BTreeMap<String, String>) -> Self {
         Self {
             go_major_version: version.major_release_version(),
             target_arch: target.arch.clone(),
             target_distro_name: target.distro_name.clone(),
             target_distro_version: target.distro_version.clone(),
+            build_settings_hash: settings_hash(&build_settings),
+            build_settings,
             cache_usage_count: 1.0,
         }
     }
+
+    /// The Go version and target fields that differ from another metadata,
+    /// which always invalidate the cache.
+    fn changed_target_fields(&self, other: &Self) -> Vec<String> {
+        [
+            (
+                "Go version",
+                self.go_major_version == other.go_major_version,
+            ),
+            ("target arch", self.target_arch == other.target_arch),
+            (
+                "target distro name",
+                self.target_distro_name == other.target_distro_name,
+            ),
+            (
+                "target distro version",
+                self.target_distro_version == other.target_distro_version,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(field, _)| field.to_string())
+        .collect()
+    }
+
+    /// The build settings that differ from another metadata. Metadata
+    /// written before build settings were recorded is treated as unchanged,
+    /// since the go command's cache is keyed by build settings anyway, and
+    /// the settings are recorded for the next build.
+    fn changed_build_settings(&self, other: &Self) -> Vec<String> {
+        if self.build_settings_hash.is_empty()
+            || self.build_settings_hash == other.build_settings_hash
+        {
+            return vec![];
+        }
+        let mut changed = self
+            .build_settings
+            .keys()
+            .chain(other.build_settings.keys())
+            .filter(|key| self.build_settings.get(*key) != other.build_settings.get(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        changed.sort();
+        changed.dedup();
+        if changed.is_empty() {
+            changed.push(String::from("build settings"));
+        }
+        changed
+    }
+}
+
+/// The settings that affect build output: the `go install` flags, and the
+/// values of the build environment variables that are set.
+pub(crate) fn build_settings(flags: &[String], go_env: &Env) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::from([(String::from("flags"), flags.join(" "))]);
+    for key in BUILD_ENV_KEYS {
+        if let Some(value) = go_env.get_string_lossy(key) {
+            settings.insert(key.to_string(), value);
+        }
+    }
+    settings
+}
+
+/// A canonical hash of build settings, over their sorted `{key}={value}` lines.
+fn settings_hash(settings: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (key, value) in settings {
+        hasher.update(format!("{key}={value}\n"));
+    }
+    hex::encode(hasher.finalize())
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -49,15 +151,21 @@ impl From<BuildLayerError> for libcnb::Error<GoBuildpackError> {
 enum BuildLayerCacheState {
     Expired,
     Invalid,
+    Changed(Vec<String>),
+    Kept(Vec<String>),
     Valid,
 }
 
-/// Create or restore the layer for cached incremental build artifacts
+/// Create or restore the layer for cached incremental build artifacts. The
+/// cache is discarded when the Go version or target changes, and, unless the
+/// policy is to keep it, when build settings change.
 pub(crate) fn handle_build_layer(
     context: &BuildContext<GoBuildpack>,
     go_version: &GoVersion,
+    build_settings: BTreeMap<String, String>,
+    policy: BuildCachePolicy,
 ) -> libcnb::Result<LayerEnv, GoBuildpackError> {
-    let mut metadata = BuildLayerMetadata::new(go_version, &context.target);
+    let mut metadata = BuildLayerMetadata::new(go_version, &context.target, build_settings);
     let layer_ref = context.cached_layer(
         layer_name!("go_build"),
         CachedLayerDefinition {
@@ -70,40 +178,37 @@ pub(crate) fn handle_build_layer(
                 )
             },
             restored_layer_action: &|restored_metadata: &BuildLayerMetadata, _| {
-                if restored_metadata.cache_usage_count >= MAX_CACHE_USAGE_COUNT {
+                let count = restored_metadata.cache_usage_count;
+                if count >= MAX_CACHE_USAGE_COUNT {
                     return (
                         RestoredLayerAction::DeleteLayer,
-                        (
-                            BuildLayerCacheState::Expired,
-                            restored_metadata.cache_usage_count,
-                        ),
+                        (BuildLayerCacheState::Expired, count),
                     );
                 }
-                if restored_metadata.go_major_version != metadata.go_major_version
-                    || restored_metadata.target_arch != metadata.target_arch
-                    || restored_metadata.target_distro_name != metadata.target_distro_name
-                    || restored_metadata.target_distro_version != metadata.target_distro_version
-                {
-                    return (
+                let mut changed = restored_metadata.changed_target_fields(&metadata);
+                let target_changed = !changed.is_empty();
+                changed.extend(restored_metadata.changed_build_settings(&metadata));
+                if changed.is_empty() {
+                    (
+                        RestoredLayerAction::KeepLayer,
+                        (BuildLayerCacheState::Valid, count),
+                    )
+                } else if target_changed || policy == BuildCachePolicy::Clear {
+                    (
                         RestoredLayerAction::DeleteLayer,
-                        (
-                            BuildLayerCacheState::Invalid,
-                            restored_metadata.cache_usage_count,
-                        ),
-                    );
-                }
-                (
-                    RestoredLayerAction::KeepLayer,
+                        (BuildLayerCacheState::Changed(changed), count),
+                    )
+                } else {
                     (
-                        BuildLayerCacheState::Valid,
-                        restored_metadata.cache_usage_count,
-                    ),
-                )
+                        RestoredLayerAction::KeepLayer,
+                        (BuildLayerCacheState::Kept(changed), count),
+                    )
+                }
             },
         },
     )?;
 
-    match layer_ref.state {
+    match &layer_ref.state {
         LayerState::Empty {
             cause: EmptyLayerCause::NewlyCreated,
         } => (),
@@ -115,9 +220,28 @@ pub(crate) fn handle_build_layer(
         } => {
             print::sub_bullet("Discarding expired Go build cache");
         }
+        LayerState::Empty {
+            cause:
+                EmptyLayerCause::RestoredLayerAction {
+                    cause: (BuildLayerCacheState::Changed(changed), _),
+                },
+        } => {
+            print::sub_bullet(format!(
+                "Discarding Go build cache, changed: {}",
+                changed.join(", ")
+            ));
+        }
         LayerState::Empty { .. } => {
             print::sub_bullet("Discarding invalid Go build cache");
         }
+        LayerState::Restored {
+            cause: (BuildLayerCacheState::Kept(changed), _),
+        } => {
+            print::sub_bullet(format!(
+                "Reusing existing Go build cache, despite changed: {}",
+                changed.join(", ")
+            ));
+        }
         LayerState::Restored { .. } => {
             print::sub_bullet("Reusing existing Go build cache");
         }
@@ -144,3 +268,76 @@ pub(crate) fn handle_build_layer(
     layer_ref.write_metadata(metadata)?;
     layer_ref.read_env()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target() -> Target {
+        Target {
+            os: String::from("linux"),
+            arch: String::from("amd64"),
+            arch_variant: None,
+            distro_name: String::from("ubuntu"),
+            distro_version: String::from("24.04"),
+        }
+    }
+
+    #[test]
+    fn collect_build_settings() {
+        let mut env = Env::new();
+        env.insert("CGO_ENABLED", "0");
+        env.insert("GOEXPERIMENT", "rangefunc");
+        env.insert("GOPATH", "/workspace/go");
+        let settings = build_settings(&[String::from("-trimpath")], &env);
+        assert_eq!(
+            settings.into_iter().collect::<Vec<_>>(),
+            [
+                (String::from("CGO_ENABLED"), String::from("0")),
+                (String::from("GOEXPERIMENT"), String::from("rangefunc")),
+                (String::from("flags"), String::from("-trimpath")),
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_build_layer_metadata() {
+        let version = GoVersion::try_from(String::from("go1.22.5")).unwrap();
+        let mut env = Env::new();
+        env.insert("CGO_ENABLED", "0");
+        let metadata = BuildLayerMetadata::new(&version, &target(), build_settings(&[], &env));
+        assert!(metadata.changed_target_fields(&metadata).is_empty());
+        assert!(metadata.changed_build_settings(&metadata).is_empty());
+
+        env.insert("CGO_ENABLED", "1");
+        env.insert("GOAMD64", "v3");
+        let changed = BuildLayerMetadata::new(
+            &GoVersion::try_from(String::from("go1.22.7")).unwrap(),
+            &target(),
+            build_settings(&[String::from("-trimpath")], &env),
+        );
+        assert_ne!(metadata.build_settings_hash, changed.build_settings_hash);
+        assert!(metadata.changed_target_fields(&changed).is_empty());
+        assert_eq!(
+            metadata.changed_build_settings(&changed),
+            ["CGO_ENABLED", "GOAMD64", "flags"]
+        );
+
+        let mut other_distro = target();
+        other_distro.distro_version = String::from("22.04");
+        let changed = BuildLayerMetadata::new(
+            &GoVersion::try_from(String::from("go1.23.0")).unwrap(),
+            &other_distro,
+            metadata.build_settings.clone(),
+        );
+        assert_eq!(
+            metadata.changed_target_fields(&changed),
+            ["Go version", "target distro version"]
+        );
+
+        let mut legacy = metadata.clone();
+        legacy.build_settings = BTreeMap::new();
+        legacy.build_settings_hash = String::new();
+        assert!(legacy.changed_build_settings(&metadata).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::cfg::Platform;
+use crate::{GoBuildpack, GoBuildpackError, cmd};
+use bullet_stream::global::print;
+use bullet_stream::style;
+use libcnb::Env;
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::UncachedLayerDefinition;
+use std::fs;
+
+#[derive(thiserror::Error, Debug)]
+#[error("Couldn't write to cross-compilation layer: {0}")]
+pub(crate) struct CrossLayerError(std::io::Error);
+
+impl From<CrossLayerError> for libcnb::Error<GoBuildpackError> {
+    fn from(value: CrossLayerError) -> Self {
+        libcnb::Error::BuildpackError(GoBuildpackError::CrossLayer(value))
+    }
+}
+
+/// Create the layer for binaries cross-compiled for other platforms, with the
+/// packages for each platform installed in a `{goos}_{goarch}` directory.
+/// `go install` refuses to cross-compile into `GOBIN`, so the binaries are
+/// installed into a temporary `GOPATH`, and moved from there.
+pub(crate) fn handle_cross_layer(
+    context: &BuildContext<GoBuildpack>,
+    platforms: &[Platform],
+    packages: &[String],
+    flags: &[String],
+    go_env: &Env,
+) -> libcnb::Result<(), GoBuildpackError> {
+    let layer_ref = context.uncached_layer(
+        layer_name!("go_cross"),
+        UncachedLayerDefinition {
+            build: false,
+            launch: true,
+        },
+    )?;
+    let gopath = layer_ref.path().join("gopath");
+    for platform in platforms {
+        if platform.goos == context.target.os && platform.goarch == context.target.arch {
+            print::warning(format!(
+                "Skipping cross-compilation for {platform}, which is the target platform."
+            ));
+            continue;
+        }
+        print::bullet(format!("Cross-compiling packages for {platform}"));
+        let mut env = go_env.clone();
+        // The go command treats an empty `GOBIN` as unset.
+        env.insert("GOBIN", "");
+        env.insert("GOOS", &platform.goos);
+        env.insert("GOARCH", &platform.goarch);
+        env.insert("GOPATH", &gopath);
+        cmd::go_install(packages, flags, &env).map_err(GoBuildpackError::GoBuild)?;
+
+        let out_dir = layer_ref.path().join(platform.dir_name());
+        fs::rename(gopath.join("bin").join(platform.dir_name()), &out_dir)
+            .map_err(CrossLayerError)?;
+        print::sub_bullet(format!(
+            "Wrote {platform} binaries to {}",
+            style::value(out_dir.to_string_lossy())
+        ));
+    }
+    if gopath.exists() {
+        fs::remove_dir_all(gopath).map_err(CrossLayerError)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::repro::{BuildRecord, Comparison, compare};
+use crate::{GoBuildpack, GoBuildpackError};
+use bullet_stream::global::print;
+use bullet_stream::style;
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+
+/// Create or restore the layer that records the digests of binaries built in
+/// reproducible mode, and report any drift from the previous build.
+pub(crate) fn handle_digests_layer(
+    context: &BuildContext<GoBuildpack>,
+    record: &BuildRecord,
+) -> libcnb::Result<(), GoBuildpackError> {
+    let layer_ref = context.cached_layer(
+        layer_name!("go_digests"),
+        CachedLayerDefinition {
+            build: false,
+            launch: false,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|previous: &BuildRecord, _| {
+                (RestoredLayerAction::KeepLayer, compare(previous, record))
+            },
+        },
+    )?;
+
+    match &layer_ref.state {
+        LayerState::Restored {
+            cause: Comparison::Identical,
+        } => {
+            print::sub_bullet("Binaries are identical to the previous build");
+        }
+        LayerState::Restored {
+            cause: Comparison::InputsChanged,
+        } => {
+            print::sub_bullet("Build inputs changed since the previous build");
+        }
+        LayerState::Restored {
+            cause: Comparison::Drift(binaries),
+        } => {
+            print::warning(format!(
+                "Binaries differ from the previous build of the same inputs: {}\n\n\
+                The build may depend on state outside the app source, such as \
+                environment variables, cgo dependencies, or build tags.",
+                if binaries.is_empty() {
+                    String::from("(binaries removed)")
+                } else {
+                    binaries
+                        .iter()
+                        .map(style::value)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            ));
+        }
+        LayerState::Empty { .. } => {
+            print::sub_bullet("Recording digests for comparison with future builds");
+        }
+    }
+    for (binary, digest) in &record.digests {
+        print::sub_bullet(format!("{binary}: sha256:{digest}"));
+    }
+    layer_ref.write_metadata(record)?;
+    Ok(())
+}
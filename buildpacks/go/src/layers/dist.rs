@@ -1,88 +1,171 @@
+use crate::inv::GoArtifact;
 use crate::{GoBuildpack, GoBuildpackError, tgz};
 use bullet_stream::global::print;
-use heroku_go_utils::vrs::GoVersion;
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
-    CachedLayerDefinition, InvalidMetadataAction, LayerRef, LayerState, RestoredLayerAction,
+    CachedLayerDefinition, InvalidMetadataAction, LayerState, RestoredLayerAction,
 };
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
-use libherokubuildpack::inventory::artifact::Artifact;
 use serde::{Deserialize, Serialize};
-use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
 
+/// The Go distributions installed in the layer, most recently used first.
+/// Each is installed in a directory named after its checksum.
 #[derive(Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub(crate) struct DistLayerMetadata {
-    artifact: Artifact<GoVersion, Sha256, Option<()>>,
+    toolchains: Vec<GoArtifact>,
 }
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum DistLayerError {
     #[error("Couldn't extract Go distribution archive: {0}")]
     Tgz(tgz::Error),
+    #[error("Couldn't remove cached Go distribution: {0}")]
+    Remove(std::io::Error),
+    #[error("Couldn't measure Go distribution size: {0}")]
+    Size(std::io::Error),
 }
 
-/// Downloads and installs the Go distribution / toolchain.
+/// Downloads and installs the Go distribution / toolchain. Up to
+/// `cache_size` recently used distributions are kept in the layer, so that
+/// switching between Go versions doesn't download them again. With `launch`,
+/// the toolchain is also available (with `GOROOT` and `PATH`) at launch, and
+/// only the current distribution is kept, since the layer is added to the
+/// image.
 pub(crate) fn handle_dist_layer(
     context: &BuildContext<GoBuildpack>,
-    artifact: &Artifact<GoVersion, Sha256, Option<()>>,
-) -> libcnb::Result<LayerRef<GoBuildpack, (), ()>, GoBuildpackError> {
+    artifact: &GoArtifact,
+    cache_size: usize,
+    launch: bool,
+) -> libcnb::Result<LayerEnv, GoBuildpackError> {
     let layer_ref = context.cached_layer(
         layer_name!("go_dist"),
         CachedLayerDefinition {
             build: true,
-            launch: false,
+            launch,
             invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
             restored_layer_action: &|restored_metadata: &DistLayerMetadata, _| {
-                if artifact == &restored_metadata.artifact {
-                    return RestoredLayerAction::KeepLayer;
-                }
-                RestoredLayerAction::DeleteLayer
+                (RestoredLayerAction::KeepLayer, restored_metadata.clone())
             },
         },
     )?;
 
-    match layer_ref.state {
-        LayerState::Restored { .. } => {
-            print::sub_bullet(format!(
-                "Reusing {} ({}-{})",
-                artifact.version, artifact.os, artifact.arch
-            ));
+    let mut toolchains = match &layer_ref.state {
+        LayerState::Restored { cause: metadata } => metadata.toolchains.clone(),
+        LayerState::Empty { .. } => vec![],
+    };
+    let goroot = layer_ref.path().join(checksum_dir(artifact));
+    if let Some(index) = toolchains
+        .iter()
+        .position(|cached| cached.checksum == artifact.checksum)
+    {
+        print::sub_bullet(format!(
+            "Reusing {} ({}-{})",
+            artifact.version, artifact.os, artifact.arch
+        ));
+        toolchains.remove(index);
+    } else {
+        print::sub_bullet(format!(
+            "Installing {} ({}-{}) from {}",
+            artifact.version, artifact.os, artifact.arch, artifact.url
+        ));
+        // Remove any partial extraction from an earlier failed build.
+        if goroot.exists() {
+            fs::remove_dir_all(&goroot).map_err(DistLayerError::Remove)?;
         }
-        LayerState::Empty { .. } => {
-            print::sub_bullet(format!(
-                "Installing {} ({}-{}) from {}",
-                artifact.version, artifact.os, artifact.arch, artifact.url
-            ));
-            tgz::fetch_strip_filter_extract_verify(
-                artifact,
-                "go",
-                ["bin", "src", "pkg", "go.env", "LICENSE"].into_iter(),
-                layer_ref.path(),
+        // Leave out standard library tests and test data, and tools for
+        // other platforms, which `go build` doesn't use.
+        let host_tools = format!("!pkg/tool/{}_{}", artifact.os, artifact.arch);
+        let extracted = tgz::fetch_strip_filter_extract_verify(
+            artifact,
+            "go",
+            ["bin", "src", "pkg", "go.env", "LICENSE"].into_iter(),
+            &[
+                "src/**/testdata",
+                "src/**/*_test.go",
+                "pkg/tool/*",
+                &host_tools,
+            ],
+            &goroot,
+        )
+        .map_err(DistLayerError::Tgz)?;
+        print::sub_bullet(format!(
+            "Installed {}, skipped {} of tests and tools for other platforms",
+            tgz::format_size(extracted.bytes),
+            tgz::format_size(extracted.excluded_bytes)
+        ));
+    }
+    toolchains.insert(0, artifact.clone());
+
+    let cache_size = if launch { 1 } else { cache_size.max(1) };
+    for evicted in toolchains.split_off(cache_size.min(toolchains.len())) {
+        print::sub_bullet(format!(
+            "Removing cached {} ({}-{})",
+            evicted.version, evicted.os, evicted.arch
+        ));
+        let dir = layer_ref.path().join(checksum_dir(&evicted));
+        if dir.exists() {
+            fs::remove_dir_all(dir).map_err(DistLayerError::Remove)?;
+        }
+    }
+
+    if launch {
+        print::sub_bullet(format!(
+            "Making the Go toolchain available at launch, adding {} to the image",
+            tgz::format_size(dir_size(layer_ref.path()).map_err(DistLayerError::Size)?)
+        ));
+    }
+
+    layer_ref.write_metadata(DistLayerMetadata { toolchains })?;
+    let scope = if launch { Scope::All } else { Scope::Build };
+    layer_ref.write_env(
+        LayerEnv::new()
+            .chainable_insert(
+                scope.clone(),
+                ModificationBehavior::Override,
+                "GOROOT",
+                &goroot,
+            )
+            .chainable_insert(
+                scope.clone(),
+                ModificationBehavior::Prepend,
+                "PATH",
+                goroot.join("bin"),
             )
-            .map_err(DistLayerError::Tgz)?;
+            .chainable_insert(scope, ModificationBehavior::Delimiter, "PATH", ":")
+            .chainable_insert(
+                Scope::Build,
+                ModificationBehavior::Override,
+                "GO111MODULE",
+                "on",
+            ),
+    )?;
+    layer_ref.read_env()
+}
 
-            layer_ref.write_metadata(DistLayerMetadata {
-                artifact: artifact.clone(),
-            })?;
-            layer_ref.write_env(
-                LayerEnv::new()
-                    .chainable_insert(
-                        Scope::Build,
-                        ModificationBehavior::Override,
-                        "GOROOT",
-                        layer_ref.path(),
-                    )
-                    .chainable_insert(
-                        Scope::Build,
-                        ModificationBehavior::Override,
-                        "GO111MODULE",
-                        "on",
-                    ),
-            )?;
+/// The total size of the files in a directory, recursively.
+fn dir_size(dir: PathBuf) -> std::io::Result<u64> {
+    let mut size = 0;
+    let mut dirs = vec![dir];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                size += entry.metadata()?.len();
+            }
         }
     }
-    Ok(layer_ref)
+    Ok(size)
+}
+
+/// The directory name for a Go distribution within the layer.
+fn checksum_dir(artifact: &GoArtifact) -> String {
+    hex::encode(&artifact.checksum.value)
 }
 
 impl From<DistLayerError> for libcnb::Error<GoBuildpackError> {
@@ -90,3 +173,17 @@ impl From<DistLayerError> for libcnb::Error<GoBuildpackError> {
         libcnb::Error::BuildpackError(GoBuildpackError::DistLayer(value))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_dir_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("bin")).unwrap();
+        fs::write(dir.path().join("bin").join("go"), [0; 100]).unwrap();
+        fs::write(dir.path().join("go.env"), [0; 10]).unwrap();
+        assert_eq!(dir_size(dir.path().to_path_buf()).unwrap(), 110);
+    }
+}
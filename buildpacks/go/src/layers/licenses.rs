@@ -0,0 +1,52 @@
+use crate::license::LicenseReport;
+use crate::{GoBuildpack, GoBuildpackError};
+use bullet_stream::global::print;
+use bullet_stream::style;
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::UncachedLayerDefinition;
+use std::fs;
+
+const REPORT_JSON: &str = "licenses.json";
+const REPORT_TEXT: &str = "licenses.txt";
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum LicensesLayerError {
+    #[error("Couldn't write license report: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Couldn't serialize license report: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<LicensesLayerError> for libcnb::Error<GoBuildpackError> {
+    fn from(value: LicensesLayerError) -> Self {
+        libcnb::Error::BuildpackError(GoBuildpackError::LicensesLayer(value))
+    }
+}
+
+/// Create the layer for the license report of modules linked into built
+/// binaries, as JSON and as a human-readable summary.
+pub(crate) fn handle_licenses_layer(
+    context: &BuildContext<GoBuildpack>,
+    report: &LicenseReport,
+) -> libcnb::Result<(), GoBuildpackError> {
+    let layer_ref = context.uncached_layer(
+        layer_name!("go_licenses"),
+        UncachedLayerDefinition {
+            build: false,
+            launch: true,
+        },
+    )?;
+    fs::write(
+        layer_ref.path().join(REPORT_JSON),
+        serde_json::to_string_pretty(report).map_err(LicensesLayerError::Json)?,
+    )
+    .map_err(LicensesLayerError::Io)?;
+    fs::write(layer_ref.path().join(REPORT_TEXT), report.to_text())
+        .map_err(LicensesLayerError::Io)?;
+    print::sub_bullet(format!(
+        "Wrote license report to {}",
+        style::value(layer_ref.path().join(REPORT_TEXT).to_string_lossy())
+    ));
+    Ok(())
+}
@@ -1,4 +1,8 @@
 pub(crate) mod build;
+pub(crate) mod cross;
 pub(crate) mod deps;
+pub(crate) mod digests;
 pub(crate) mod dist;
+pub(crate) mod licenses;
+pub(crate) mod runtime;
 pub(crate) mod target;
@@ -0,0 +1,84 @@
+use crate::{GoBuildpack, GoBuildpackError};
+use bullet_stream::global::print;
+use bullet_stream::style;
+use heroku_go_utils::vrs::GoVersion;
+use libcnb::additional_buildpack_binary_path;
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::UncachedLayerDefinition;
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+
+const GOTRACEBACK_DEFAULT: &str = "all";
+
+// Go 1.25 and newer set `GOMAXPROCS` from the CPU limit themselves, and
+// update it when the limit changes, unless `GOMAXPROCS` is set.
+const CGROUP_GOMAXPROCS_SINCE: &str = "1.25";
+
+/// Create the layer for launch-time runtime configuration of Go binaries.
+/// Environment variables are written as defaults, so any value set in the
+/// launch environment takes precedence.
+pub(crate) fn handle_runtime_layer(
+    context: &BuildContext<GoBuildpack>,
+    godebug: &[(String, String)],
+    go_version: &GoVersion,
+) -> libcnb::Result<(), GoBuildpackError> {
+    let layer_ref = context.uncached_layer(
+        layer_name!("go_runtime"),
+        UncachedLayerDefinition {
+            build: false,
+            launch: true,
+        },
+    )?;
+
+    let mut layer_env = LayerEnv::new();
+    let mut defaults = vec![];
+    // The `default` key is only meaningful in go.mod, and is already compiled
+    // into binaries by the Go toolchain.
+    let godebug_value = godebug
+        .iter()
+        .filter(|(key, _)| key != "default")
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    if !godebug_value.is_empty() {
+        defaults.push(("GODEBUG", godebug_value));
+    }
+    defaults.push(("GOTRACEBACK", GOTRACEBACK_DEFAULT.to_string()));
+
+    for (key, value) in defaults {
+        print::sub_bullet(format!(
+            "Setting {} by default (set {} to override)",
+            style::value(format!("{key}={value}")),
+            style::value(key)
+        ));
+        layer_env.insert(Scope::Launch, ModificationBehavior::Default, key, value);
+    }
+
+    let runtime_gomaxprocs = GoVersion::try_from(CGROUP_GOMAXPROCS_SINCE.to_string())
+        .is_ok_and(|since| go_version.major_release_version() >= since);
+    if runtime_gomaxprocs {
+        layer_env.insert(
+            Scope::Launch,
+            ModificationBehavior::Override,
+            "HEROKU_GO_CGROUP_GOMAXPROCS",
+            "false",
+        );
+        print::sub_bullet(format!(
+            "Setting {} from container limits ({} is set by the Go runtime)",
+            style::value("GOMEMLIMIT"),
+            style::value("GOMAXPROCS")
+        ));
+    } else {
+        print::sub_bullet(format!(
+            "Setting {} and {} from container limits",
+            style::value("GOMEMLIMIT"),
+            style::value("GOMAXPROCS")
+        ));
+    }
+    layer_ref.write_env(layer_env)?;
+
+    layer_ref.write_exec_d_programs([(
+        "go_runtime_limits",
+        additional_buildpack_binary_path!("go_runtime_limits"),
+    )])
+}
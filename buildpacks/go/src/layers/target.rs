@@ -1,8 +1,16 @@
+use crate::buildinfo::BuildInfo;
+use crate::cfg::ProcessSettings;
+use crate::sbom;
 use crate::{GoBuildpack, GoBuildpackError};
+use bullet_stream::global::print;
+use bullet_stream::style;
+use heroku_go_utils::vrs::GoVersion;
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
-use libcnb::layer::UncachedLayerDefinition;
-use libcnb::layer_env::{LayerEnv, Scope};
+use libcnb::layer::{LayerRef, UncachedLayerDefinition};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libherokubuildpack::inventory::artifact::Artifact;
+use sha2::Sha256;
 use std::fs;
 use std::io;
 
@@ -19,7 +27,7 @@ impl From<TargetLayerError> for libcnb::Error<GoBuildpackError> {
 // Create the layer for compiled Go binaries
 pub(crate) fn handle_target_layer(
     context: &BuildContext<GoBuildpack>,
-) -> libcnb::Result<LayerEnv, GoBuildpackError> {
+) -> libcnb::Result<LayerRef<GoBuildpack, (), ()>, GoBuildpackError> {
     let layer_ref = context.uncached_layer(
         layer_name!("go_target"),
         UncachedLayerDefinition {
@@ -35,5 +43,54 @@ pub(crate) fn handle_target_layer(
         "GOBIN",
         bin_dir,
     ))?;
-    layer_ref.read_env()
+    Ok(layer_ref)
+}
+
+/// Write process-specific launch environment variables to the target layer.
+/// Variables are written as defaults, so any value set in the launch
+/// environment takes precedence.
+pub(crate) fn write_process_env(
+    layer_ref: &LayerRef<GoBuildpack, (), ()>,
+    process_settings: &[ProcessSettings],
+) -> libcnb::Result<(), GoBuildpackError> {
+    let mut layer_env = layer_ref.read_env()?;
+    for settings in process_settings {
+        for (key, value) in &settings.env {
+            print::sub_bullet(format!(
+                "Setting {} for process {} by default (set {} to override)",
+                style::value(format!("{key}={value}")),
+                style::value(&settings.name),
+                style::value(key)
+            ));
+            layer_env.insert(
+                Scope::Process(settings.name.clone()),
+                ModificationBehavior::Default,
+                key,
+                value,
+            );
+        }
+    }
+    layer_ref.write_env(layer_env)
+}
+
+/// Write `CycloneDX` and SPDX SBOMs for the binaries in the target layer.
+pub(crate) fn write_target_sboms(
+    layer_ref: &LayerRef<GoBuildpack, (), ()>,
+    build_info: &[BuildInfo],
+    toolchain: &Artifact<GoVersion, Sha256, Option<()>>,
+    buildpack_version: &str,
+) -> libcnb::Result<(), GoBuildpackError> {
+    let mut modules = build_info
+        .iter()
+        .flat_map(|info| info.deps.iter().map(|dep| &dep.built().path))
+        .collect::<Vec<_>>();
+    modules.sort();
+    modules.dedup();
+    print::sub_bullet(format!(
+        "Recording {} and {} modules in {} binaries",
+        toolchain.version,
+        modules.len(),
+        build_info.len()
+    ));
+    layer_ref.write_sboms(&sbom::build_sboms(build_info, toolchain, buildpack_version))
 }
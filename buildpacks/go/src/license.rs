@@ -0,0 +1,489 @@
+use crate::buildinfo::BuildInfo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The identifier for license files that don't match a known license.
+pub(crate) const UNKNOWN_LICENSE: &str = "unknown";
+
+/// The identifier for modules without license files.
+pub(crate) const NO_LICENSE: &str = "none";
+
+/// The directory of license files, besides those at the top level of a
+/// module, as used by REUSE compliant projects.
+const LICENSES_DIR: &str = "LICENSES";
+
+/// Known license texts, identified by phrases that appear in them. More
+/// specific licenses are listed before those they contain phrases of (e.g.
+/// the LGPL and AGPL, which refer to the GPL).
+const LICENSE_PHRASES: &[(&str, &[&str])] = &[
+    (
+        "AGPL-3.0",
+        &["GNU AFFERO GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    (
+        "LGPL-3.0",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    (
+        "LGPL-2.1",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"],
+    ),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("MPL-2.0", &["Mozilla Public License", "2.0"]),
+    ("EPL-2.0", &["Eclipse Public License", "2.0"]),
+    (
+        "BSD-3-Clause",
+        &[
+            "Redistribution and use in source and binary forms",
+            "Neither the name",
+        ],
+    ),
+    (
+        "BSD-3-Clause",
+        &[
+            "Redistribution and use in source and binary forms",
+            "names of its contributors",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["Redistribution and use in source and binary forms"],
+    ),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+    (
+        "ISC",
+        &["Permission to use, copy, modify, and/or distribute this software for any purpose"],
+    ),
+    (
+        "Unlicense",
+        &["This is free and unencumbered software released into the public domain"],
+    ),
+    ("CC0-1.0", &["CC0 1.0 Universal"]),
+];
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("Couldn't read license files in {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Found modules with denied licenses:\n\n{}", .0.join("\n"))]
+    Denied(Vec<String>),
+}
+
+/// The licenses found for the modules linked into built binaries.
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub(crate) struct LicenseReport {
+    pub(crate) modules: Vec<ModuleLicenses>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct ModuleLicenses {
+    pub(crate) path: String,
+    pub(crate) version: String,
+    pub(crate) licenses: Vec<LicenseFile>,
+}
+
+impl ModuleLicenses {
+    /// The distinct licenses of the module, or `none` when it has no license
+    /// files.
+    fn license_ids(&self) -> Vec<&str> {
+        let mut licenses = self
+            .licenses
+            .iter()
+            .map(|license| license.license.as_str())
+            .collect::<Vec<_>>();
+        licenses.sort_unstable();
+        licenses.dedup();
+        if licenses.is_empty() {
+            licenses.push(NO_LICENSE);
+        }
+        licenses
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct LicenseFile {
+    pub(crate) file: String,
+    pub(crate) license: String,
+}
+
+/// Where to find the source of the modules linked into binaries.
+pub(crate) struct ModuleSources<'a> {
+    pub(crate) app_dir: &'a Path,
+    /// The Go module cache (`GOMODCACHE`), when modules aren't vendored.
+    pub(crate) mod_cache: Option<&'a Path>,
+    /// The Go toolchain (`GOROOT`), for the standard library license.
+    pub(crate) goroot: Option<&'a Path>,
+    pub(crate) vendored: bool,
+}
+
+impl LicenseReport {
+    /// Find and classify the license files of the standard library and each
+    /// module linked into built binaries.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error when a module directory exists but can't be read.
+    pub(crate) fn new(build_info: &[BuildInfo], sources: &ModuleSources) -> Result<Self, Error> {
+        let mut modules = BTreeMap::new();
+        if let Some(info) = build_info.first() {
+            modules.insert(
+                ("stdlib".to_string(), info.go_version.clone()),
+                sources.goroot.map(Path::to_path_buf),
+            );
+        }
+        for dep in build_info.iter().flat_map(|info| &info.deps) {
+            let built = dep.built();
+            let dir = if built.path.starts_with('.') || built.path.starts_with('/') {
+                Some(sources.app_dir.join(&built.path))
+            } else if sources.vendored {
+                Some(sources.app_dir.join("vendor").join(&dep.path))
+            } else {
+                sources.mod_cache.map(|mod_cache| {
+                    mod_cache.join(format!(
+                        "{}@{}",
+                        escape_module_path(&built.path),
+                        escape_module_path(&built.version)
+                    ))
+                })
+            };
+            modules.insert((built.path.clone(), built.version.clone()), dir);
+        }
+
+        let mut report = LicenseReport::default();
+        for ((path, version), dir) in modules {
+            let licenses = match dir {
+                Some(dir) if dir.is_dir() => find_licenses(&dir)?,
+                _ => vec![],
+            };
+            report.modules.push(ModuleLicenses {
+                path,
+                version,
+                licenses,
+            });
+        }
+        Ok(report)
+    }
+
+    /// The number of modules using each license. Modules without license
+    /// files are counted as `none`, and license files that aren't identified
+    /// as `unknown`.
+    pub(crate) fn summary(&self) -> BTreeMap<&str, usize> {
+        let mut summary = BTreeMap::new();
+        for module in &self.modules {
+            for license in module.license_ids() {
+                *summary.entry(license).or_default() += 1;
+            }
+        }
+        summary
+    }
+
+    /// A human-readable summary of the report.
+    pub(crate) fn to_text(&self) -> String {
+        let mut lines = vec![String::from("License summary:")];
+        lines.extend(
+            self.summary()
+                .into_iter()
+                .map(|(license, count)| format!("  {license}: {count}")),
+        );
+        lines.push(String::new());
+        lines.push(String::from("Modules:"));
+        lines.extend(self.modules.iter().map(|module| {
+            let licenses = module
+                .licenses
+                .iter()
+                .map(|license| format!("{} ({})", license.license, license.file))
+                .collect::<Vec<_>>();
+            format!(
+                "  {} {}: {}",
+                module.path,
+                module.version,
+                if licenses.is_empty() {
+                    format!("{NO_LICENSE} (no license file found)")
+                } else {
+                    licenses.join(", ")
+                }
+            )
+        }));
+        lines.join("\n") + "\n"
+    }
+
+    /// Check the report against a list of denied licenses. A denied license
+    /// matches an SPDX identifier exactly, or as a prefix followed by a `-`,
+    /// ignoring case, so `GPL` denies `GPL-2.0` and `GPL-3.0`, but not
+    /// `LGPL-2.1`. Modules without license files are denied by `none`, and
+    /// license files that aren't identified by `unknown`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error listing each module with a denied license.
+    pub(crate) fn check_denied(&self, denied: &[String]) -> Result<(), Error> {
+        let matches = |license: &str| {
+            denied.iter().any(|denied| {
+                let license = license.to_ascii_lowercase();
+                let denied = denied.to_ascii_lowercase();
+                license == denied || license.starts_with(&format!("{denied}-"))
+            })
+        };
+        let violations = self
+            .modules
+            .iter()
+            .flat_map(|module| {
+                let no_license = (module.licenses.is_empty() && matches(NO_LICENSE)).then(|| {
+                    format!(
+                        "{} {}: {NO_LICENSE} (no license file found)",
+                        module.path, module.version
+                    )
+                });
+                module
+                    .licenses
+                    .iter()
+                    .filter(|license| matches(&license.license))
+                    .map(move |license| {
+                        format!(
+                            "{} {}: {} ({})",
+                            module.path, module.version, license.license, license.file
+                        )
+                    })
+                    .chain(no_license)
+            })
+            .collect::<Vec<_>>();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Denied(violations))
+        }
+    }
+}
+
+/// Find and classify license files at the top level of a module directory,
+/// and each file in its `LICENSES` directory.
+fn find_licenses(dir: &Path) -> Result<Vec<LicenseFile>, Error> {
+    let io_error = |error| Error::Io(dir.to_path_buf(), error);
+    let file_names = |dir: &Path| -> std::io::Result<Vec<String>> {
+        Ok(fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect())
+    };
+    let mut names = file_names(dir)
+        .map_err(io_error)?
+        .into_iter()
+        .filter(|name| is_license_file(name))
+        .collect::<Vec<_>>();
+    let licenses_dir = dir.join(LICENSES_DIR);
+    if licenses_dir.is_dir() {
+        names.extend(
+            file_names(&licenses_dir)
+                .map_err(io_error)?
+                .into_iter()
+                .map(|name| format!("{LICENSES_DIR}/{name}")),
+        );
+    }
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let contents = fs::read(dir.join(&name)).map_err(io_error)?;
+            Ok(LicenseFile {
+                license: classify_license(&String::from_utf8_lossy(&contents)).to_string(),
+                file: name,
+            })
+        })
+        .collect()
+}
+
+fn is_license_file(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    ["LICENSE", "LICENCE", "COPYING", "UNLICENSE"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// Identify a license text by its SPDX identifier.
+fn classify_license(text: &str) -> &'static str {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    LICENSE_PHRASES
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map_or(UNKNOWN_LICENSE, |(license, _)| license)
+}
+
+/// Escape a module path or version for the module cache, where each
+/// uppercase letter is replaced by `!` and the lowercase letter.
+fn escape_module_path(path: &str) -> String {
+    let mut escaped = String::new();
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildinfo::parse_go_version_m;
+
+    const MIT: &str = "MIT License\n\nPermission is hereby granted, free of charge, to any person\nobtaining a copy";
+    const AGPL: &str = "GNU AFFERO GENERAL PUBLIC LICENSE\n   Version 3, 19 November 2007";
+    const BSD_3: &str = "Redistribution and use in source and binary forms, with or without\nmodification, are permitted...\n   * Neither the name of Google Inc. nor the names";
+
+    #[test]
+    fn classify_licenses() {
+        assert_eq!(classify_license(MIT), "MIT");
+        assert_eq!(classify_license(AGPL), "AGPL-3.0");
+        assert_eq!(classify_license(BSD_3), "BSD-3-Clause");
+        assert_eq!(
+            classify_license("GNU LESSER GENERAL PUBLIC LICENSE\nVersion 2.1, February 1999"),
+            "LGPL-2.1"
+        );
+        assert_eq!(
+            classify_license("Apache License\n  Version 2.0, January 2004"),
+            "Apache-2.0"
+        );
+        assert_eq!(classify_license("All rights reserved."), UNKNOWN_LICENSE);
+    }
+
+    #[test]
+    fn escape_module_paths() {
+        assert_eq!(
+            escape_module_path("github.com/BurntSushi/toml"),
+            "github.com/!burnt!sushi/toml"
+        );
+        assert_eq!(escape_module_path("v1.0.0-RC1"), "v1.0.0-!r!c1");
+    }
+
+    fn write_files(dir: &Path, files: &[(&str, &str)]) {
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn license_report_from_mod_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        write_files(
+            dir.path(),
+            &[
+                ("goroot/LICENSE", BSD_3),
+                ("cache/github.com/!burnt!sushi/toml@v1.3.2/COPYING", MIT),
+                ("cache/github.com/!burnt!sushi/toml@v1.3.2/toml.go", ""),
+                ("cache/example.com/agpl@v0.1.0/LICENSE.md", AGPL),
+                ("cache/example.com/agpl@v0.1.0/LICENSE-MIT", MIT),
+                ("app/local/go.mod", ""),
+            ],
+        );
+        let build_info = parse_go_version_m(
+            "/layers/go_target/bin/server: go1.22.1\n\
+             \tdep\tgithub.com/BurntSushi/toml\tv1.3.2\th1:abc=\n\
+             \tdep\texample.com/agpl\tv0.1.0\th1:def=\n\
+             \tdep\texample.com/local\tv0.0.0\n\
+             \t=>\t./local\t(devel)\t\n",
+        );
+        let report = LicenseReport::new(
+            &build_info,
+            &ModuleSources {
+                app_dir: &dir.path().join("app"),
+                mod_cache: Some(&dir.path().join("cache")),
+                goroot: Some(&dir.path().join("goroot")),
+                vendored: false,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.to_text(),
+            "License summary:\n  \
+            AGPL-3.0: 1\n  \
+            BSD-3-Clause: 1\n  \
+            MIT: 2\n  \
+            none: 1\n\
+            \n\
+            Modules:\n  \
+            ./local (devel): none (no license file found)\n  \
+            example.com/agpl v0.1.0: MIT (LICENSE-MIT), AGPL-3.0 (LICENSE.md)\n  \
+            github.com/BurntSushi/toml v1.3.2: MIT (COPYING)\n  \
+            stdlib go1.22.1: BSD-3-Clause (LICENSE)\n"
+        );
+        assert!(report.check_denied(&["GPL".to_string()]).is_ok());
+        assert_eq!(
+            report
+                .check_denied(&["agpl".to_string(), "LGPL-2.1".to_string()])
+                .unwrap_err()
+                .to_string(),
+            "Found modules with denied licenses:\n\nexample.com/agpl v0.1.0: AGPL-3.0 (LICENSE.md)"
+        );
+        assert_eq!(
+            report
+                .check_denied(&["none".to_string(), "unknown".to_string()])
+                .unwrap_err()
+                .to_string(),
+            "Found modules with denied licenses:\n\n./local (devel): none (no license file found)"
+        );
+    }
+
+    #[test]
+    fn license_report_from_vendor() {
+        let dir = tempfile::tempdir().unwrap();
+        write_files(
+            dir.path(),
+            &[
+                ("vendor/example.com/dep/LICENSE", MIT),
+                ("vendor/example.com/reuse/LICENSES/AGPL-3.0-only.txt", AGPL),
+                (
+                    "vendor/example.com/reuse/LICENSES/custom.txt",
+                    "All rights reserved.",
+                ),
+                ("vendor/example.com/reuse/README.md", ""),
+            ],
+        );
+        let build_info = parse_go_version_m(
+            "/layers/go_target/bin/server: go1.22.1\n\
+             \tdep\texample.com/dep\tv1.0.0\n\
+             \tdep\texample.com/reuse\tv1.0.0\n",
+        );
+        let report = LicenseReport::new(
+            &build_info,
+            &ModuleSources {
+                app_dir: dir.path(),
+                mod_cache: None,
+                goroot: None,
+                vendored: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            report.summary(),
+            BTreeMap::from([
+                ("AGPL-3.0", 1),
+                ("MIT", 1),
+                (NO_LICENSE, 1),
+                (UNKNOWN_LICENSE, 1)
+            ])
+        );
+        assert_eq!(
+            report.modules[1].licenses,
+            [
+                LicenseFile {
+                    file: String::from("LICENSES/AGPL-3.0-only.txt"),
+                    license: String::from("AGPL-3.0"),
+                },
+                LicenseFile {
+                    file: String::from("LICENSES/custom.txt"),
+                    license: String::from(UNKNOWN_LICENSE),
+                },
+            ]
+        );
+    }
+}
@@ -3,33 +3,51 @@
 // to be able selectively opt out of coverage for functions/lines/modules.
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
+mod buildenv;
+mod buildinfo;
 mod cfg;
 mod cmd;
+mod diag;
+mod gomod;
+mod inv;
 mod layers;
+mod license;
+mod plan;
 mod proc;
+mod procfile;
+mod repro;
+mod sbom;
 mod tgz;
+mod vendor;
+mod vuln;
 
 use bullet_stream::global::print;
 use bullet_stream::style;
 use heroku_go_utils::vrs::GoVersion;
 use indoc::formatdoc;
-use layers::build::{BuildLayerError, handle_build_layer};
+use inv::{GoArtifact, GoInventory};
+use layers::build::{BuildLayerError, build_settings, handle_build_layer};
+use layers::cross::{CrossLayerError, handle_cross_layer};
 use layers::deps::{DepsLayerError, handle_deps_layer};
+use layers::digests::handle_digests_layer;
 use layers::dist::{DistLayerError, handle_dist_layer};
-use layers::target::{TargetLayerError, handle_target_layer};
+use layers::licenses::{LicensesLayerError, handle_licenses_layer};
+use layers::runtime::handle_runtime_layer;
+use layers::target::{
+    TargetLayerError, handle_target_layer, write_process_env, write_target_sboms,
+};
 use libcnb::build::{BuildContext, BuildResult, BuildResultBuilder};
 use libcnb::data::build_plan::BuildPlanBuilder;
 use libcnb::data::launch::{LaunchBuilder, Process};
 use libcnb::detect::{DetectContext, DetectResult, DetectResultBuilder};
 use libcnb::generic::GenericMetadata;
 use libcnb::generic::GenericPlatform;
+use libcnb::layer::LayerRef;
 use libcnb::layer_env::Scope;
-use libcnb::{Buildpack, Env, buildpack_main};
+use libcnb::{Buildpack, Env, Target, buildpack_main};
+use libherokubuildpack::inventory::ParseInventoryError;
 use libherokubuildpack::inventory::artifact::{Arch, Os};
-use libherokubuildpack::inventory::{Inventory, ParseInventoryError};
-use sha2::Sha256;
-use std::env::consts;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 #[cfg(test)]
@@ -85,68 +103,88 @@ impl Buildpack for GoBuildpack {
         }
 
         let inv = INVENTORY
-            .parse::<Inventory<GoVersion, Sha256, Option<()>>>()
+            .parse::<GoInventory>()
             .map_err(GoBuildpackError::InventoryParse)?;
 
+        if !context.app_dir.join("go.mod").exists() {
+            return build_toolchain_only(&context, &inv, started);
+        }
+
         let config = cfg::read_gomod_config(context.app_dir.join("go.mod"))
             .map_err(GoBuildpackError::GoModConfig)?;
-        let requirement = config.version.unwrap_or_default();
-        print::sub_bullet(format!("Detected Go version requirement: {requirement}"));
+        let requirement = go_version_requirement(&context, config.version.as_ref())?;
+        let artifact = resolve_artifact(&inv, &context.target, &requirement)?;
 
-        let artifact = match (consts::OS.parse::<Os>(), consts::ARCH.parse::<Arch>()) {
-            (Ok(os), Ok(arch)) => inv.resolve(os, arch, &requirement),
-            (_, _) => None,
-        }
-        .ok_or(GoBuildpackError::VersionResolution(requirement.clone()))?;
-
-        print::sub_bullet(format!(
-            "Resolved Go version: {} ({}-{})",
-            artifact.version, artifact.os, artifact.arch
-        ));
+        let go_debug_directives = cfg::find_go_debug_directives(&context.app_dir);
+        cfg::print_gomod_settings(&config, &go_debug_directives, &artifact.version);
+        go_env = with_build_env(&config, &artifact.version, go_env)?;
 
         print::bullet("Installing Go distribution");
-        go_env = handle_dist_layer(&context, artifact)?
-            .read_env()?
-            .apply(Scope::Build, &go_env);
+        go_env = handle_dist_layer(
+            &context,
+            artifact,
+            config.toolchain.cache_size,
+            config.toolchain.launch,
+        )?
+        .apply(Scope::Build, &go_env);
 
         print::bullet("Building Go binaries");
         if Path::exists(&context.app_dir.join("vendor").join("modules.txt")) {
             print::sub_bullet("Using vendored Go modules");
+            let count = vendor::check_vendor_dir(&context.app_dir, &config.gomod)
+                .map_err(GoBuildpackError::Vendor)?;
+            print::sub_bullet(format!("Verified {count} vendored modules against go.mod"));
         } else {
             go_env = handle_deps_layer(&context)?.apply(Scope::Build, &go_env);
+            download_modules(&config, &go_env)?;
         }
 
-        go_env = handle_target_layer(&context)?.apply(Scope::Build, &go_env);
+        let target_layer = handle_target_layer(&context)?;
+        go_env = target_layer.read_env()?.apply(Scope::Build, &go_env);
 
-        go_env = handle_build_layer(&context, &artifact.version)?.apply(Scope::Build, &go_env);
+        let (build_env, flags) = install_settings(&config, &go_env);
+        go_env = handle_build_layer(
+            &context,
+            &artifact.version,
+            build_settings(&flags, &build_env),
+            config.build_cache,
+        )?
+        .apply(Scope::Build, &build_env);
 
-        print::sub_bullet("Resolving Go modules");
-        let packages = config.packages.unwrap_or(
-            // Use `go list` to determine packages to build. Do this eagerly,
-            // even if the result is unused because it has the side effect of
-            // downloading any required go modules.
-            cmd::go_list(&go_env).map_err(GoBuildpackError::GoList)?,
-        );
+        let packages = if let Some(packages) = config.packages.clone() {
+            packages
+        } else {
+            // Use `go list` to determine packages to build.
+            print::sub_bullet("Finding main packages");
+            cmd::go_list(&go_env).map_err(GoBuildpackError::GoList)?
+        };
 
         print::bullet("Building packages:");
         for pkg in &packages {
             print::sub_bullet(pkg);
         }
-        cmd::go_install(&packages, &go_env).map_err(GoBuildpackError::GoBuild)?;
+        install_packages(
+            &context,
+            &config,
+            &packages,
+            &flags,
+            &go_env,
+            &target_layer,
+            artifact,
+        )?;
+        inspect_binaries(&context, &config, &go_env, &target_layer, artifact)?;
 
-        let mut procs: Vec<Process> = vec![];
-        if Path::exists(&context.app_dir.join("Procfile")) {
-            print::bullet("Skipping launch process registration (Procfile detected)");
-        } else {
-            print::bullet("Registering launch processes:");
-            procs = proc::build_procs(&packages).map_err(GoBuildpackError::Proc)?;
-            for proc in &procs {
-                print::sub_bullet(format!(
-                    "{}: {}",
-                    proc.r#type,
-                    style::command(proc.command.join(" "))
-                ));
-            }
+        print::bullet("Configuring launch environment");
+        handle_runtime_layer(&context, &config.gomod.godebug, &artifact.version)?;
+
+        let procs = register_launch_processes(&context, &config, &packages, &go_env)?;
+        if config
+            .process_settings
+            .iter()
+            .any(|settings| !settings.env.is_empty())
+        {
+            print::bullet("Configuring process environment");
+            write_process_env(&target_layer, &config.process_settings)?;
         }
 
         print::all_done(&Some(started));
@@ -158,18 +196,39 @@ impl Buildpack for GoBuildpack {
     fn on_error(&self, error: libcnb::Error<Self::Error>) {
         match error {
             libcnb::Error::BuildpackError(bp_err) => {
-                let err_string = bp_err.to_string();
+                let mut err_string = bp_err.to_string();
+                if let GoBuildpackError::GoBuild(cmd_err)
+                | GoBuildpackError::GoList(cmd_err)
+                | GoBuildpackError::GoModDownload(cmd_err) = &bp_err
+                {
+                    for diagnosis in cmd_err.diagnoses() {
+                        err_string.push_str("\n\n");
+                        err_string.push_str(diagnosis.guidance().trim_end());
+                    }
+                }
                 let err_ctx = match bp_err {
                     GoBuildpackError::BuildLayer(_) => "build layer",
+                    GoBuildpackError::BuildEnv(_) => "build settings",
+                    GoBuildpackError::CrossLayer(_) => "cross-compilation layer",
                     GoBuildpackError::DepsLayer(_) => "dependency layer",
                     GoBuildpackError::DistLayer(_) => "distribution layer",
                     GoBuildpackError::TargetLayer(_) => "target layer",
                     GoBuildpackError::GoModConfig(_) => "go.mod",
                     GoBuildpackError::InventoryParse(_) => "inventory parse",
-                    GoBuildpackError::VersionResolution(_) => "version resolution",
+                    GoBuildpackError::BuildPlan(_) => "build plan",
+                    GoBuildpackError::VersionResolution(..) => "version resolution",
                     GoBuildpackError::GoBuild(_) => "go build",
                     GoBuildpackError::GoList(_) => "go list",
+                    GoBuildpackError::GoModDownload(_) => "go mod download",
+                    GoBuildpackError::GoModVerify(_) => "go mod verify",
+                    GoBuildpackError::GoVersion(_) => "go version",
                     GoBuildpackError::Proc(_) => "launch process type",
+                    GoBuildpackError::Procfile(_) => "Procfile",
+                    GoBuildpackError::Vuln(_) => "vulnerability scan",
+                    GoBuildpackError::License(_) => "license report",
+                    GoBuildpackError::LicensesLayer(_) => "license report layer",
+                    GoBuildpackError::Reproducible(_) => "reproducible build",
+                    GoBuildpackError::Vendor(_) => "vendored modules",
                 };
                 print::error(format!(
                     "Heroku Go Buildpack {err_ctx} error\n\n{err_string}"
@@ -182,17 +241,497 @@ impl Buildpack for GoBuildpack {
     }
 }
 
+/// Install only the Go toolchain, without building anything, for other
+/// buildpacks that require `go` when there is no go.mod.
+fn build_toolchain_only(
+    context: &BuildContext<GoBuildpack>,
+    inv: &GoInventory,
+    started: Instant,
+) -> libcnb::Result<BuildResult, GoBuildpackError> {
+    print::sub_bullet("No go.mod found, installing the Go toolchain only");
+    let requirement = go_version_requirement(context, None)?;
+    let artifact = resolve_artifact(inv, &context.target, &requirement)?;
+    let launch = plan::go_entries(&context.buildpack_plan)
+        .map_err(GoBuildpackError::BuildPlan)?
+        .iter()
+        .any(|entry| entry.launch);
+
+    print::bullet("Installing Go distribution");
+    handle_dist_layer(context, artifact, cfg::DEFAULT_TOOLCHAIN_CACHE_SIZE, launch)?;
+
+    print::all_done(&Some(started));
+    BuildResultBuilder::new().build()
+}
+
+/// Combine the Go version requirements from go.mod (if any) and the `go`
+/// entries in the buildpack plan, logging where each came from.
+fn go_version_requirement(
+    context: &BuildContext<GoBuildpack>,
+    gomod_requirement: Option<&semver::VersionReq>,
+) -> Result<semver::VersionReq, GoBuildpackError> {
+    let mut requirements = vec![];
+    if let Some(requirement) = gomod_requirement {
+        print::sub_bullet(format!(
+            "Detected Go version requirement: {requirement} (go.mod)"
+        ));
+        requirements.push(requirement.clone());
+    }
+    let plan_entries =
+        plan::go_entries(&context.buildpack_plan).map_err(GoBuildpackError::BuildPlan)?;
+    for requirement in
+        plan::version_requirements(&plan_entries).map_err(GoBuildpackError::BuildPlan)?
+    {
+        print::sub_bullet(format!(
+            "Detected Go version requirement: {requirement} (build plan)"
+        ));
+        requirements.push(requirement);
+    }
+    let requirement = plan::combine_requirements(&requirements);
+    if requirements.len() > 1 {
+        print::sub_bullet(format!("Combined Go version requirement: {requirement}"));
+    }
+    Ok(requirement)
+}
+
+/// Resolve the Go distribution to install from the inventory, for the
+/// target platform of the image.
+fn resolve_artifact<'a>(
+    inv: &'a GoInventory,
+    target: &Target,
+    requirement: &semver::VersionReq,
+) -> Result<&'a GoArtifact, GoBuildpackError> {
+    let os = target.os.parse::<Os>().ok();
+    let arch = target.arch.parse::<Arch>().ok();
+    let artifact = match (os, arch) {
+        (Some(os), Some(arch)) => inv.resolve(os, arch, requirement),
+        (_, _) => None,
+    }
+    .ok_or_else(|| {
+        GoBuildpackError::VersionResolution(
+            requirement.clone(),
+            Box::new(inv::suggest_versions(inv, os, arch, requirement)),
+        )
+    })?;
+
+    print::sub_bullet(format!(
+        "Resolved Go version: {} ({}-{})",
+        artifact.version, artifact.os, artifact.arch
+    ));
+    Ok(artifact)
+}
+
+/// Download the modules needed to build the main module, reporting how many
+/// were reused from the module cache, and verify them when configured.
+fn download_modules(config: &cfg::GoModConfig, go_env: &Env) -> Result<(), GoBuildpackError> {
+    let cached_zips = go_env
+        .get("GOMODCACHE")
+        .map(|mod_cache| cmd::cached_module_zips(Path::new(&mod_cache)))
+        .unwrap_or_default();
+    let modules = cmd::go_mod_download(go_env).map_err(GoBuildpackError::GoModDownload)?;
+    let downloaded = modules
+        .iter()
+        .filter(|module| module.downloaded(&cached_zips))
+        .count();
+    print::sub_bullet(format!(
+        "Downloaded {downloaded} modules, reused {} from the module cache",
+        modules.len() - downloaded
+    ));
+    if config.verify_modules {
+        cmd::go_mod_verify(go_env).map_err(GoBuildpackError::GoModVerify)?;
+    }
+    Ok(())
+}
+
+/// Add the configured microarchitecture levels and experiments to the
+/// environment, once they're checked against the Go version.
+fn with_build_env(
+    config: &cfg::GoModConfig,
+    go_version: &GoVersion,
+    mut go_env: Env,
+) -> Result<Env, GoBuildpackError> {
+    for (key, value) in
+        buildenv::env_vars(&config.build_env, go_version).map_err(GoBuildpackError::BuildEnv)?
+    {
+        print::sub_bullet(format!(
+            "Build setting: {}",
+            style::value(format!("{key}={value}"))
+        ));
+        go_env.insert(key, value);
+    }
+    Ok(go_env)
+}
+
+/// The environment and `go install` flags for the build, with reproducible
+/// build settings when configured.
+fn install_settings(config: &cfg::GoModConfig, go_env: &Env) -> (Env, Vec<String>) {
+    if config.reproducible {
+        print::sub_bullet("Using reproducible build settings");
+        (repro::build_env(go_env), repro::build_flags(go_env))
+    } else {
+        (go_env.clone(), vec![])
+    }
+}
+
+/// Build and install the packages, recording binary digests for reproducible
+/// builds, and for other platforms, when configured.
+fn install_packages(
+    context: &BuildContext<GoBuildpack>,
+    config: &cfg::GoModConfig,
+    packages: &[String],
+    flags: &[String],
+    go_env: &Env,
+    target_layer: &LayerRef<GoBuildpack, (), ()>,
+    toolchain: &GoArtifact,
+) -> libcnb::Result<(), GoBuildpackError> {
+    cmd::go_install(packages, flags, go_env).map_err(GoBuildpackError::GoBuild)?;
+
+    if !config.cross_compile.is_empty() {
+        handle_cross_layer(context, &config.cross_compile, packages, flags, go_env)?;
+    }
+
+    if config.reproducible {
+        print::bullet("Recording binary digests");
+        let inputs = cmd::go_list_deps(packages, go_env)
+            .map_err(GoBuildpackError::GoList)?
+            .iter()
+            .filter(|package| !package.standard)
+            .flat_map(cmd::ListedPackage::files)
+            .collect::<Vec<_>>();
+        let record = repro::record_build(
+            &context.app_dir,
+            &inputs,
+            &target_layer.path().join("bin"),
+            &toolchain.version.to_string(),
+            &build_settings(flags, go_env),
+        )
+        .map_err(GoBuildpackError::Reproducible)?;
+        handle_digests_layer(context, &record)?;
+    }
+    Ok(())
+}
+
+/// Write the SBOM, vulnerability scan, and license report for the built
+/// binaries.
+fn inspect_binaries(
+    context: &BuildContext<GoBuildpack>,
+    config: &cfg::GoModConfig,
+    go_env: &Env,
+    target_layer: &LayerRef<GoBuildpack, (), ()>,
+    toolchain: &GoArtifact,
+) -> libcnb::Result<(), GoBuildpackError> {
+    print::bullet("Writing SBOM");
+    let build_info = cmd::go_version_m(&target_layer.path().join("bin"), go_env)
+        .map(|output| buildinfo::parse_go_version_m(&output))
+        .map_err(GoBuildpackError::GoVersion)?;
+    write_target_sboms(
+        target_layer,
+        &build_info,
+        toolchain,
+        &context.buildpack_descriptor.buildpack.version.to_string(),
+    )?;
+    scan_vulnerabilities(context, config, &build_info)?;
+    report_licenses(context, config, &build_info, go_env)
+}
+
+/// Determine the launch processes to register, from either the Procfile (when
+/// requested) or the installed packages and configured processes.
+fn register_launch_processes(
+    context: &BuildContext<GoBuildpack>,
+    config: &cfg::GoModConfig,
+    packages: &[String],
+    go_env: &Env,
+) -> Result<Vec<Process>, GoBuildpackError> {
+    let mut procs: Vec<Process> = vec![];
+    let procfile_path = context.app_dir.join("Procfile");
+    if Path::exists(&procfile_path) {
+        let binaries = proc::binary_names(packages);
+        if config.register_procfile {
+            let procfile =
+                procfile::read_procfile(&procfile_path).map_err(GoBuildpackError::Procfile)?;
+            for warning in procfile::check_binaries(&procfile, &binaries, &context.app_dir) {
+                print::warning(warning);
+            }
+            print::bullet("Registering launch processes from Procfile:");
+            procs = procfile::build_procs(&procfile).map_err(GoBuildpackError::Procfile)?;
+        } else {
+            // Another buildpack registers the Procfile processes, and reports
+            // any errors in it, so they're only warnings here.
+            match procfile::read_procfile(&procfile_path) {
+                Ok(procfile) => {
+                    for warning in procfile::check_binaries(&procfile, &binaries, &context.app_dir)
+                    {
+                        print::warning(warning);
+                    }
+                }
+                Err(error) => print::warning(error.to_string()),
+            }
+            print::bullet("Skipping launch process registration (Procfile detected)");
+        }
+    } else {
+        let web_pkg = select_web_package(config, packages, go_env)?;
+        let renamed = proc::process_names(packages)
+            .into_iter()
+            .filter(proc::ProcessName::is_renamed)
+            .map(|name| format!("{} -> {}", name.binary, name.name))
+            .collect::<Vec<_>>();
+        if !renamed.is_empty() {
+            print::warning(formatdoc! {"
+                Some binary names aren't valid launch process names, and were renamed:
+
+                {}
+            ", renamed.join("\n")});
+        }
+        print::bullet("Registering launch processes:");
+        procs = proc::build_procs(packages, web_pkg.as_deref().map(proc::exec_name))
+            .and_then(|procs| proc::merge_procs(procs, packages, &config.processes))
+            .map_err(GoBuildpackError::Proc)?;
+    }
+    if procs.is_empty() {
+        if config
+            .process_settings
+            .iter()
+            .any(|settings| settings.working_dir.is_some())
+        {
+            print::warning(
+                "Ignoring `// +heroku processDir` directives, since this buildpack isn't \
+                registering launch processes.",
+            );
+        }
+    } else {
+        proc::apply_process_settings(&mut procs, &config.process_settings)
+            .map_err(GoBuildpackError::Proc)?;
+    }
+    for proc in &procs {
+        print::sub_bullet(format!(
+            "{}: {}",
+            proc.r#type,
+            style::command(
+                proc.command
+                    .iter()
+                    .chain(&proc.args)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        ));
+    }
+    Ok(procs)
+}
+
+/// Scan built binaries (or `go.sum`) for known vulnerabilities, when a
+/// vulnerability database is configured in `go.mod` or provided by a binding.
+fn scan_vulnerabilities(
+    context: &BuildContext<GoBuildpack>,
+    config: &cfg::GoModConfig,
+    build_info: &[buildinfo::BuildInfo],
+) -> Result<(), GoBuildpackError> {
+    let Some(db_dir) = config
+        .vuln
+        .db
+        .as_ref()
+        .map(|path| context.app_dir.join(path))
+        .or_else(|| {
+            std::env::var_os("SERVICE_BINDING_ROOT")
+                .and_then(|binding_root| vuln::find_binding(Path::new(&binding_root)))
+        })
+    else {
+        return Ok(());
+    };
+
+    print::bullet("Scanning for known vulnerabilities");
+    let db = vuln::VulnDb::read(&db_dir).map_err(GoBuildpackError::Vuln)?;
+    if db.unreadable() > 0 {
+        print::warning(format!(
+            "Skipped {} files in the vulnerability database that aren't valid OSV entries.",
+            db.unreadable()
+        ));
+    }
+    let gosum_path = context.app_dir.join("go.sum");
+    let modules = if !config.vuln.scan_gosum {
+        vuln::binary_modules(build_info)
+    } else if gosum_path.exists() {
+        vuln::gosum_modules(&gosum_path).map_err(GoBuildpackError::Vuln)?
+    } else {
+        vec![]
+    };
+    print::sub_bullet(format!(
+        "Checking {} modules from {} against {} advisories in {}",
+        modules.len(),
+        if config.vuln.scan_gosum {
+            "go.sum"
+        } else {
+            "built binaries"
+        },
+        db.len(),
+        style::value(db_dir.to_string_lossy())
+    ));
+
+    let findings = db.scan(&modules);
+    if findings.is_empty() {
+        print::sub_bullet("No known vulnerabilities found");
+    } else {
+        print::warning(formatdoc! {"
+            Found known vulnerabilities:
+
+            {}
+        ", findings.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")});
+    }
+    if let Some(threshold) = config.vuln.fail_on {
+        let unknown = findings
+            .iter()
+            .filter(|finding| finding.severity == vuln::Severity::Unknown)
+            .count();
+        if threshold > vuln::Severity::Unknown && unknown > 0 {
+            print::warning(format!(
+                "{unknown} of the known vulnerabilities have no known severity, and don't fail \
+                the build. Use {} to fail the build for them.",
+                style::value("// +heroku vulnFailOn unknown")
+            ));
+        }
+        vuln::check_threshold(&findings, threshold).map_err(GoBuildpackError::Vuln)?;
+    }
+    Ok(())
+}
+
+/// Write a report of the licenses of the modules linked into built binaries,
+/// and check it against the denied licenses configured in `go.mod`.
+fn report_licenses(
+    context: &BuildContext<GoBuildpack>,
+    config: &cfg::GoModConfig,
+    build_info: &[buildinfo::BuildInfo],
+    go_env: &Env,
+) -> libcnb::Result<(), GoBuildpackError> {
+    print::bullet("Writing license report");
+    let mod_cache = go_env.get("GOMODCACHE").map(PathBuf::from);
+    let goroot = go_env.get("GOROOT").map(PathBuf::from);
+    let report = license::LicenseReport::new(
+        build_info,
+        &license::ModuleSources {
+            app_dir: &context.app_dir,
+            mod_cache: mod_cache.as_deref(),
+            goroot: goroot.as_deref(),
+            vendored: Path::exists(&context.app_dir.join("vendor").join("modules.txt")),
+        },
+    )
+    .map_err(GoBuildpackError::License)?;
+    for (license, count) in report.summary() {
+        print::sub_bullet(format!("{}: {count} modules", style::value(license)));
+    }
+    handle_licenses_layer(context, &report)?;
+    if !config.deny_licenses.is_empty() {
+        report
+            .check_denied(&config.deny_licenses)
+            .map_err(GoBuildpackError::License)?;
+        let summary = report.summary();
+        for (license, reason) in [
+            (license::NO_LICENSE, "have no license file"),
+            (
+                license::UNKNOWN_LICENSE,
+                "have license files that weren't identified",
+            ),
+        ] {
+            if let Some(count) = summary.get(license)
+                && !config
+                    .deny_licenses
+                    .iter()
+                    .any(|denied| denied.eq_ignore_ascii_case(license))
+            {
+                print::warning(format!(
+                    "{count} modules {reason}, so they weren't checked against the denied \
+                    licenses. Add `{license}` to `// +heroku denyLicenses` to deny them."
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Determine which package should run as the `web` process when no package
+/// has a `web` suffix and no `web` process is configured. Returns `None` when
+/// there's no choice to make.
+fn select_web_package(
+    config: &cfg::GoModConfig,
+    packages: &[String],
+    go_env: &Env,
+) -> Result<Option<String>, GoBuildpackError> {
+    if let Some(package) = &config.web_package {
+        // Packages may be relative paths or patterns, so the web package
+        // is also matched by its binary name.
+        if !packages.contains(package)
+            && !proc::binary_names(packages).contains(&proc::exec_name(package).to_string())
+        {
+            return Err(GoBuildpackError::Proc(proc::Error::UnknownWebPackage {
+                package: package.clone(),
+                available: packages.to_vec(),
+            }));
+        }
+    }
+    if proc::process_names(packages)
+        .iter()
+        .any(|process_name| process_name.name == "web")
+        || config.processes.iter().any(|process| process.name == "web")
+    {
+        if let Some(package) = &config.web_package {
+            print::warning(format!(
+                "Ignoring `// +heroku webPackage {package}`, since there's already a \
+                 `web` package or process"
+            ));
+        }
+        return Ok(None);
+    }
+    if let Some(package) = &config.web_package {
+        print::bullet(format!(
+            "Using {} for the web process (configured with `// +heroku webPackage`)",
+            style::value(package)
+        ));
+        return Ok(Some(package.clone()));
+    }
+    if packages.len() < 2 {
+        return Ok(None);
+    }
+
+    print::bullet("Choosing the web process package");
+    let imports = cmd::go_list_imports(packages, go_env).map_err(GoBuildpackError::GoList)?;
+    Ok(proc::choose_web_package(
+        &imports.packages,
+        &imports.imports,
+        config.gomod.module.as_deref(),
+    )
+    .map(|web| {
+        print::sub_bullet(format!(
+            "Using {} for the web process: {}",
+            style::value(&web.package),
+            web.reasons.join(", ")
+        ));
+        print::sub_bullet(format!(
+            "To choose a different package, add {} to go.mod",
+            style::value("// +heroku webPackage <import path>")
+        ));
+        web.package
+    }))
+}
+
 #[derive(thiserror::Error, Debug)]
 enum GoBuildpackError {
     #[error("{0}")]
     BuildLayer(#[from] BuildLayerError),
+    #[error("{0}")]
+    BuildEnv(buildenv::Error),
     #[error("Couldn't run `go build`: {0}")]
     GoBuild(cmd::Error),
     #[error("Couldn't run `go list`: {0}")]
     GoList(cmd::Error),
+    #[error("Couldn't run `go mod download`: {0}")]
+    GoModDownload(cmd::Error),
+    #[error("Couldn't run `go mod verify`: {0}")]
+    GoModVerify(cmd::Error),
+    #[error("Couldn't run `go version -m`: {0}")]
+    GoVersion(cmd::Error),
     #[error("{0}")]
     GoModConfig(#[from] cfg::ReadGoModConfigError),
     #[error("{0}")]
+    CrossLayer(#[from] CrossLayerError),
+    #[error("{0}")]
     DepsLayer(#[from] DepsLayerError),
     #[error("{0}")]
     DistLayer(#[from] DistLayerError),
@@ -200,10 +739,24 @@ enum GoBuildpackError {
     TargetLayer(#[from] TargetLayerError),
     #[error("Couldn't parse go artifact inventory: {0}")]
     InventoryParse(ParseInventoryError),
-    #[error("Couldn't resolve go version for: {0}")]
-    VersionResolution(semver::VersionReq),
+    #[error("{0}")]
+    BuildPlan(plan::Error),
+    #[error("Couldn't resolve go version for: {0}{1}")]
+    VersionResolution(semver::VersionReq, Box<inv::VersionSuggestions>),
     #[error("Launch process error: {0}")]
     Proc(proc::Error),
+    #[error("{0}")]
+    Procfile(procfile::Error),
+    #[error("{0}")]
+    Vuln(vuln::Error),
+    #[error("{0}")]
+    License(license::Error),
+    #[error("{0}")]
+    LicensesLayer(#[from] LicensesLayerError),
+    #[error("{0}")]
+    Reproducible(repro::Error),
+    #[error("{0}")]
+    Vendor(vendor::Error),
 }
 
 impl From<GoBuildpackError> for libcnb::Error<GoBuildpackError> {
@@ -0,0 +1,147 @@
+use heroku_go_utils::vrs::parse_go_version_requirement;
+use libcnb::data::buildpack_plan::BuildpackPlan;
+use semver::VersionReq;
+
+/// Metadata of a `go` entry in the buildpack plan, as required by this or
+/// another buildpack, e.g. `{ name = "go", metadata = { version = "~1.22" } }`.
+/// With `launch = true`, the Go toolchain is also made available at launch.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct GoPlanMetadata {
+    pub(crate) version: Option<String>,
+    pub(crate) launch: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("Invalid `go` build plan entry: expected `{0}` to be a {1}")]
+    Metadata(&'static str, &'static str),
+    #[error("Couldn't parse Go version requirement `{0}` from the build plan: {1}")]
+    Version(String, semver::Error),
+}
+
+/// Read the metadata of the `go` entries in the buildpack plan.
+///
+/// # Errors
+///
+/// Will return an error when the metadata of an entry is invalid.
+pub(crate) fn go_entries(plan: &BuildpackPlan) -> Result<Vec<GoPlanMetadata>, Error> {
+    plan.entries
+        .iter()
+        .filter(|entry| entry.name == "go")
+        .map(|entry| {
+            let version = entry
+                .metadata
+                .get("version")
+                .map(|value| value.as_str().ok_or(Error::Metadata("version", "string")))
+                .transpose()?;
+            let launch = entry
+                .metadata
+                .get("launch")
+                .map(|value| value.as_bool().ok_or(Error::Metadata("launch", "boolean")))
+                .transpose()?;
+            Ok(GoPlanMetadata {
+                version: version.map(ToString::to_string),
+                launch: launch.unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// The Go version requirements of `go` build plan entries, in the formats
+/// accepted by the `// +heroku goVersion` directive.
+///
+/// # Errors
+///
+/// Will return an error when a version requirement can't be parsed.
+pub(crate) fn version_requirements(entries: &[GoPlanMetadata]) -> Result<Vec<VersionReq>, Error> {
+    entries
+        .iter()
+        .filter_map(|entry| entry.version.as_deref())
+        .map(|version| {
+            parse_go_version_requirement(version)
+                .map_err(|e| Error::Version(version.to_string(), e))
+        })
+        .collect()
+}
+
+/// Combine version requirements into one that only matches versions that
+/// match all of them.
+pub(crate) fn combine_requirements<'a>(
+    requirements: impl IntoIterator<Item = &'a VersionReq>,
+) -> VersionReq {
+    VersionReq {
+        comparators: requirements
+            .into_iter()
+            .flat_map(|requirement| requirement.comparators.clone())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libcnb::data::buildpack_plan::Entry;
+
+    fn entry(name: &str, metadata: &str) -> Entry {
+        Entry {
+            name: name.to_string(),
+            metadata: metadata.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn read_plan_version_requirements() {
+        let plan = BuildpackPlan {
+            entries: vec![
+                entry("go", ""),
+                entry("go", "version = \"~1.22\"\nlaunch = true"),
+                entry("node", "version = \"20\""),
+                entry("go", "version = \"go1.22.5\""),
+            ],
+        };
+        let entries = go_entries(&plan).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries.iter().map(|entry| entry.launch).collect::<Vec<_>>(),
+            [false, true, false]
+        );
+        let requirements = version_requirements(&entries).unwrap();
+        assert_eq!(
+            requirements
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["~1.22", "=1.22.5"]
+        );
+
+        let combined = combine_requirements(&requirements);
+        assert_eq!(combined.to_string(), "~1.22, =1.22.5");
+        assert!(combined.matches(&semver::Version::new(1, 22, 5)));
+        assert!(!combined.matches(&semver::Version::new(1, 22, 6)));
+        assert_eq!(combine_requirements(&[]), VersionReq::STAR);
+    }
+
+    #[test]
+    fn read_invalid_plan_entries() {
+        let plan = BuildpackPlan {
+            entries: vec![entry("go", "version = 1")],
+        };
+        assert!(matches!(go_entries(&plan), Err(Error::Metadata(..))));
+        let plan = BuildpackPlan {
+            entries: vec![entry("go", "launch = \"yes\"")],
+        };
+        assert!(matches!(
+            go_entries(&plan),
+            Err(Error::Metadata("launch", _))
+        ));
+
+        let entries = [GoPlanMetadata {
+            version: Some("><1".to_string()),
+            launch: false,
+        }];
+        assert!(matches!(
+            version_requirements(&entries),
+            Err(Error::Version(..))
+        ));
+    }
+}
@@ -1,50 +1,112 @@
+use crate::cfg::{ProcessConfig, ProcessSettings};
 use libcnb::data::{
-    launch::{Process, ProcessBuilder, ProcessType, ProcessTypeError},
+    launch::{Process, ProcessBuilder, ProcessType, ProcessTypeError, WorkingDirectory},
     process_type,
 };
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::PathBuf;
 
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum Error {
-    #[error("Invalid Go package import path: {0}")]
-    ImportPath(String),
     #[error("Invalid CNB process name: {0}")]
     ProcessName(#[from] ProcessTypeError),
+    #[error("Process {process} runs {binary}, which is not a built binary. Built binaries: {}", .available.join(", "))]
+    UnknownBinary {
+        process: String,
+        binary: String,
+        available: Vec<String>,
+    },
+    #[error("Settings are configured for process {process}, which isn't registered. Registered processes: {}", .available.join(", "))]
+    UnknownProcess {
+        process: String,
+        available: Vec<String>,
+    },
+    #[error("Web package {package} is not a built package. Built packages: {}", .available.join(", "))]
+    UnknownWebPackage {
+        package: String,
+        available: Vec<String>,
+    },
+}
+
+/// Names that suggest a package is a web server.
+const WEB_NAMES: [&str; 3] = ["server", "api", "app"];
+
+/// Import paths (or import path prefixes) of HTTP servers and frameworks.
+const HTTP_IMPORTS: [&str; 7] = [
+    "net/http",
+    "github.com/gin-gonic/gin",
+    "github.com/labstack/echo",
+    "github.com/gofiber/fiber",
+    "github.com/valyala/fasthttp",
+    "github.com/gorilla/mux",
+    "github.com/go-chi/chi",
+];
+
+/// A package chosen to run as the `web` process, with the reasons it was
+/// chosen.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct WebPackage {
+    pub(crate) package: String,
+    pub(crate) reasons: Vec<String>,
+}
+
+/// A CNB process name derived from the name of a built binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcessName {
+    pub(crate) binary: String,
+    pub(crate) name: String,
+}
+
+impl ProcessName {
+    /// Whether the process name differs from the binary name.
+    pub(crate) fn is_renamed(&self) -> bool {
+        self.binary != self.name
+    }
 }
 
 /// Turns a list of go packages into a CNB process list. Any package with
 /// a `web` suffix will be flagged as default process. If there are packages
 /// and none with a `web` suffix, a `web` process will be created for the
-/// first package.
+/// `web_binary` binary, or the binary of the first package. The web binary is
+/// matched by name, since packages may be relative paths or patterns (e.g.
+/// `./cmd/server`) while the web package is an import path.
 ///
 /// # Examples
 ///
 /// ```
 /// let procs = heroku_go_utils::proc::build_procs(
-///                &["github.com/heroku/maple".to_string()]
+///                &["github.com/heroku/maple".to_string()],
+///                None,
 ///              ).unwrap();
 /// ```
 ///
+/// Process names are derived from binary names with [`process_names`].
+///
 /// # Errors
 ///
-/// Invalid go packages (those without a `'/'`) and go packages with suffixes
-/// that don't satisfy CNB process naming conventions will error.
-pub(crate) fn build_procs(pkgs: &[String]) -> Result<Vec<Process>, Error> {
+/// Will error if a sanitized process name doesn't satisfy CNB process naming
+/// conventions, which shouldn't happen.
+pub(crate) fn build_procs(
+    pkgs: &[String],
+    web_binary: Option<&str>,
+) -> Result<Vec<Process>, Error> {
     let mut procs: Vec<Process> = vec![];
-    for pkg in pkgs {
-        let proc_name = pkg
-            .rsplit_once('/')
-            .map(|(_path, name)| name)
-            .ok_or_else(|| Error::ImportPath(pkg.clone()))?
-            .parse::<ProcessType>()?;
+    let mut web_proc = None;
+    for process_name in process_names(pkgs) {
+        let proc_name = process_name.name.parse::<ProcessType>()?;
+        let is_web_binary = web_binary == Some(process_name.binary.as_str());
 
         procs.push(
-            ProcessBuilder::new(proc_name.clone(), [proc_name.to_string()])
+            ProcessBuilder::new(proc_name.clone(), [process_name.binary])
                 .default(proc_name.to_string() == "web")
                 .build(),
         );
+        if is_web_binary && web_proc.is_none() {
+            web_proc = procs.last().cloned();
+        }
     }
     if !procs.iter().any(|p| p.default)
-        && let Some(proc) = procs.clone().first()
+        && let Some(proc) = web_proc.or_else(|| procs.first().cloned())
     {
         procs.push(
             ProcessBuilder::new(process_type!("web"), &proc.command)
@@ -55,13 +117,272 @@ pub(crate) fn build_procs(pkgs: &[String]) -> Result<Vec<Process>, Error> {
     Ok(procs)
 }
 
+/// Chooses which of several packages to run as the `web` process when none
+/// has a `web` suffix. Packages are ranked by whether they import an HTTP
+/// server or framework, directly or through other packages of the module,
+/// are named like a server (`server`, `api`, `app`), or are the
+/// `cmd/{module name}` package of the module. Ties are broken by package
+/// order. Returns `None` when there's nothing to choose from.
+///
+/// `imports` maps the packages, and the packages they depend on, to the
+/// import paths they directly import.
+pub(crate) fn choose_web_package(
+    packages: &[String],
+    imports: &BTreeMap<String, Vec<String>>,
+    module: Option<&str>,
+) -> Option<WebPackage> {
+    let mut best: Option<(usize, WebPackage)> = None;
+    for package in packages {
+        let (score, reasons) = rank_web_package(package, imports, module);
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((
+                score,
+                WebPackage {
+                    package: package.clone(),
+                    reasons,
+                },
+            ));
+        }
+    }
+    best.map(|(score, mut web)| {
+        if score == 0 {
+            web.reasons = vec![String::from(
+                "first package, no other package looks like a web server",
+            )];
+        }
+        web
+    })
+}
+
+fn rank_web_package(
+    package: &str,
+    imports: &BTreeMap<String, Vec<String>>,
+    module: Option<&str>,
+) -> (usize, Vec<String>) {
+    let mut score = 0;
+    let mut reasons = vec![];
+    let name = exec_name(package);
+
+    if let Some((http_import, importer)) = find_http_import(package, imports, module) {
+        score += 4;
+        if importer == package {
+            reasons.push(format!("imports {http_import}"));
+        } else {
+            reasons.push(format!("imports {http_import} through {importer}"));
+        }
+    }
+    if WEB_NAMES.contains(&name) {
+        score += 2;
+        reasons.push(format!("is named {name}"));
+    }
+    if let Some(module) = module
+        && let module_name = exec_name(module)
+        && package == format!("{module}/cmd/{module_name}")
+    {
+        score += 1;
+        reasons.push(format!("is the cmd/{module_name} package of the module"));
+    }
+    (score, reasons)
+}
+
+/// Finds an HTTP server or framework import of a package, or of the packages
+/// of the module that it imports, directly or indirectly. Returns the import
+/// and the package that imports it, preferring the closest one.
+fn find_http_import<'a>(
+    package: &'a str,
+    imports: &'a BTreeMap<String, Vec<String>>,
+    module: Option<&str>,
+) -> Option<(&'a str, &'a str)> {
+    let in_module = |import: &str| {
+        module.is_some_and(|module| {
+            import == module
+                || import
+                    .strip_prefix(module)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    };
+    let mut queue = VecDeque::from([package]);
+    let mut seen = BTreeSet::from([package]);
+    while let Some(importer) = queue.pop_front() {
+        for import in imports.get(importer).into_iter().flatten() {
+            if HTTP_IMPORTS.iter().any(|prefix| {
+                import.as_str() == *prefix || import.starts_with(&format!("{prefix}/"))
+            }) {
+                return Some((import, importer));
+            }
+            if in_module(import) && seen.insert(import) {
+                queue.push_back(import);
+            }
+        }
+    }
+    None
+}
+
+/// Merges configured processes into a list of inferred processes. A
+/// configured process replaces an inferred process of the same name. If a
+/// configured process is flagged as default, no other process is default.
+/// Otherwise, a configured `web` process is default.
+///
+/// # Errors
+///
+/// Configured processes with invalid CNB process names, or that run a binary
+/// other than one of the packages built, will error.
+pub(crate) fn merge_procs(
+    mut procs: Vec<Process>,
+    pkgs: &[String],
+    configured: &[ProcessConfig],
+) -> Result<Vec<Process>, Error> {
+    let available = binary_names(pkgs);
+    let explicit_default = configured.iter().any(|config| config.default);
+
+    for config in configured {
+        if !available.contains(&config.binary) {
+            return Err(Error::UnknownBinary {
+                process: config.name.clone(),
+                binary: config.binary.clone(),
+                available,
+            });
+        }
+        let proc_name = config.name.parse::<ProcessType>()?;
+        let proc = ProcessBuilder::new(proc_name.clone(), [&config.binary])
+            .args(&config.args)
+            .default(config.default || (!explicit_default && config.name == "web"))
+            .working_directory(
+                config
+                    .working_dir
+                    .as_ref()
+                    .map_or(WorkingDirectory::App, |dir| {
+                        WorkingDirectory::Directory(PathBuf::from(dir))
+                    }),
+            )
+            .build();
+
+        if proc.default {
+            for existing in &mut procs {
+                existing.default = false;
+            }
+        }
+        match procs
+            .iter_mut()
+            .find(|existing| existing.r#type == proc_name)
+        {
+            Some(existing) => *existing = proc,
+            None => procs.push(proc),
+        }
+    }
+    Ok(procs)
+}
+
+/// Applies configured process settings to a list of processes, setting the
+/// working directory of each configured process. Environment variables are
+/// written to the launch environment separately.
+///
+/// # Errors
+///
+/// Settings for processes that aren't in the list will error.
+pub(crate) fn apply_process_settings(
+    procs: &mut [Process],
+    process_settings: &[ProcessSettings],
+) -> Result<(), Error> {
+    for settings in process_settings {
+        let Some(proc) = procs
+            .iter_mut()
+            .find(|proc| proc.r#type.to_string() == settings.name)
+        else {
+            return Err(Error::UnknownProcess {
+                process: settings.name.clone(),
+                available: procs.iter().map(|proc| proc.r#type.to_string()).collect(),
+            });
+        };
+        if let Some(dir) = &settings.working_dir {
+            proc.working_directory = WorkingDirectory::Directory(PathBuf::from(dir));
+        }
+    }
+    Ok(())
+}
+
+/// The names of the binaries `go install` produces for a list of packages.
+pub(crate) fn binary_names(pkgs: &[String]) -> Vec<String> {
+    pkgs.iter().map(|pkg| exec_name(pkg).to_string()).collect()
+}
+
+/// Derive a valid CNB process name for the binary of each package. Runs of
+/// characters that aren't allowed in process names are replaced with a `-`,
+/// and valid names are kept as they are. When two binaries would get the
+/// same process name, later ones get a numeric suffix (`-2`, `-3`, ...), so
+/// names only depend on the order of the packages.
+pub(crate) fn process_names(pkgs: &[String]) -> Vec<ProcessName> {
+    let mut names: Vec<ProcessName> = vec![];
+    for binary in binary_names(pkgs) {
+        let base = sanitize_process_name(&binary);
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while names.iter().any(|existing| existing.name == name) {
+            name = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        names.push(ProcessName { binary, name });
+    }
+    names
+}
+
+/// Replace characters that aren't allowed in CNB process names
+/// (`^[[:alnum:]._-]+$`) with a `-`, dropping them at the start and end.
+fn sanitize_process_name(binary: &str) -> String {
+    let mut name = String::new();
+    let mut replaced = false;
+    for c in binary.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+            if replaced {
+                name.push('-');
+                replaced = false;
+            }
+            name.push(c);
+        } else if !name.is_empty() {
+            replaced = true;
+        }
+    }
+    if name.is_empty() {
+        String::from("proc")
+    } else {
+        name
+    }
+}
+
+/// The default executable name for an import path, following the rules of
+/// the go command: the last path element, unless it is a major version
+/// suffix (e.g. `v2`), in which case the element before it is used. So
+/// `example.com/tool/v2` installs `tool`, while `gopkg.in/yaml.v2` installs
+/// `yaml.v2`.
+pub(crate) fn exec_name(import_path: &str) -> &str {
+    match import_path.rsplit_once('/') {
+        Some((dir, elem)) if is_version_element(elem) => dir.rsplit('/').next().unwrap_or(dir),
+        Some((_dir, elem)) => elem,
+        None => import_path,
+    }
+}
+
+/// Whether a path element is a major version suffix: `v` followed by a
+/// number of at least 2, without leading zeroes.
+fn is_version_element(elem: &str) -> bool {
+    elem.strip_prefix('v').is_some_and(|digits| {
+        !digits.is_empty()
+            && digits != "1"
+            && !digits.starts_with('0')
+            && digits.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn build_procs_adds_web() {
-        let procs = build_procs(&[String::from("github.com/kubernetes/kubernetes")])
+        let procs = build_procs(&[String::from("github.com/kubernetes/kubernetes")], None)
             .expect("unexpected error with build_procs");
         for (i, name) in ["kubernetes", "web"].iter().enumerate() {
             let proc = procs.get(i).expect("missing process in build_procs");
@@ -72,24 +393,421 @@ mod tests {
 
     #[test]
     fn build_procs_does_not_dup_web() {
-        let procs = build_procs(&[String::from("example.com/web")])
+        let procs = build_procs(&[String::from("example.com/web")], None)
             .expect("unexpected error with build_procs");
         assert_eq!(procs.len(), 1);
         assert_eq!(procs[0].command, ["web"]);
     }
 
     #[test]
-    fn build_procs_invalid_pkg() {
-        let err = build_procs(&[String::from("foobar")]).unwrap_err();
-        assert_eq!(format!("{err}"), "Invalid Go package import path: foobar");
+    fn build_procs_versioned_paths() {
+        let procs = build_procs(
+            &[
+                String::from("example.com/tool/v2"),
+                String::from("example.com/app/cmd/worker/v13"),
+                String::from("gopkg.in/yaml.v2"),
+                String::from("gopkg.in/x.v1"),
+            ],
+            None,
+        )
+        .unwrap();
+        let summary = procs
+            .iter()
+            .map(|p| (p.r#type.to_string(), p.command.join(" ")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (String::from("tool"), String::from("tool")),
+                (String::from("worker"), String::from("worker")),
+                (String::from("yaml.v2"), String::from("yaml.v2")),
+                (String::from("x.v1"), String::from("x.v1")),
+                (String::from("web"), String::from("tool")),
+            ]
+        );
+    }
+
+    #[test]
+    fn exec_names() {
+        for (import_path, expected) in [
+            ("example.com/tool", "tool"),
+            ("example.com/tool/v2", "tool"),
+            ("example.com/tool/v10", "tool"),
+            ("example.com/tool/v1", "v1"),
+            ("example.com/tool/v0", "v0"),
+            ("example.com/tool/v02", "v02"),
+            ("example.com/tool/v2beta", "v2beta"),
+            ("example.com/v2", "example.com"),
+            ("gopkg.in/yaml.v2", "yaml.v2"),
+            ("gopkg.in/x.v1", "x.v1"),
+            ("v2", "v2"),
+        ] {
+            assert_eq!(exec_name(import_path), expected, "{import_path}");
+        }
+    }
+
+    #[test]
+    fn build_procs_uses_web_pkg() {
+        let pkgs = [
+            String::from("example.com/app/cmd/migrate"),
+            String::from("example.com/app/cmd/server"),
+        ];
+        let procs = build_procs(&pkgs, Some("server")).unwrap();
+        assert_eq!(procs.len(), 3);
+        assert_eq!(procs[2].r#type.to_string(), "web");
+        assert_eq!(procs[2].command, ["server"]);
+        assert!(procs[2].default);
     }
 
     #[test]
-    fn build_procs_invalid_process() {
-        let err = build_procs(&[String::from("example.com/[]")]).unwrap_err();
+    fn build_procs_uses_web_pkg_with_relative_paths() {
+        // `// +heroku install ./cmd/migrate ./cmd/server`, with the web
+        // package chosen by import path.
+        let pkgs = [String::from("./cmd/migrate"), String::from("./cmd/server")];
+        let web_binary = exec_name("example.com/app/cmd/server");
+        let procs = build_procs(&pkgs, Some(web_binary)).unwrap();
+        assert_eq!(
+            procs
+                .iter()
+                .map(|p| (p.r#type.to_string(), p.command.join(" ")))
+                .collect::<Vec<_>>(),
+            [
+                (String::from("migrate"), String::from("migrate")),
+                (String::from("server"), String::from("server")),
+                (String::from("web"), String::from("server")),
+            ]
+        );
+    }
+
+    fn imports(pkg: &str, imports: &[&str]) -> (String, Vec<String>) {
+        (
+            pkg.to_string(),
+            imports.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    /// Choose between packages, with the direct imports of each, and of
+    /// the packages they depend on.
+    fn choose(
+        packages: &[(String, Vec<String>)],
+        deps: &[(String, Vec<String>)],
+        module: Option<&str>,
+    ) -> Option<WebPackage> {
+        choose_web_package(
+            &packages
+                .iter()
+                .map(|(package, _)| package.clone())
+                .collect::<Vec<_>>(),
+            &packages.iter().chain(deps).cloned().collect(),
+            module,
+        )
+    }
+
+    #[test]
+    fn choose_web_package_prefers_http_imports() {
+        let web = choose(
+            &[
+                imports("example.com/app/cmd/api", &["fmt"]),
+                imports(
+                    "example.com/app/cmd/frontend",
+                    &["fmt", "github.com/labstack/echo/v4"],
+                ),
+            ],
+            &[],
+            Some("example.com/app"),
+        )
+        .unwrap();
+        assert_eq!(
+            web,
+            WebPackage {
+                package: String::from("example.com/app/cmd/frontend"),
+                reasons: vec![String::from("imports github.com/labstack/echo/v4")],
+            }
+        );
+    }
+
+    #[test]
+    fn choose_web_package_combines_reasons() {
+        let web = choose(
+            &[
+                imports("example.com/app/cmd/worker", &["net/http"]),
+                imports("example.com/app/cmd/app", &["net/http"]),
+            ],
+            &[],
+            Some("example.com/app"),
+        )
+        .unwrap();
+        assert_eq!(web.package, "example.com/app/cmd/app");
+        assert_eq!(
+            web.reasons,
+            [
+                "imports net/http",
+                "is named app",
+                "is the cmd/app package of the module"
+            ]
+        );
+    }
+
+    #[test]
+    fn choose_web_package_falls_back_to_first() {
+        let web = choose(
+            &[
+                imports("example.com/app/cmd/migrate", &["fmt"]),
+                imports("example.com/app/cmd/worker", &["os"]),
+            ],
+            &[],
+            None,
+        )
+        .unwrap();
+        assert_eq!(web.package, "example.com/app/cmd/migrate");
+        assert_eq!(
+            web.reasons,
+            ["first package, no other package looks like a web server"]
+        );
+        assert_eq!(choose(&[], &[], None), None);
+    }
+
+    #[test]
+    fn choose_web_package_follows_module_imports() {
+        let packages = [
+            imports(
+                "example.com/app/cmd/worker",
+                &[
+                    "example.com/app/internal/queue",
+                    "github.com/aws/aws-sdk-go",
+                ],
+            ),
+            imports(
+                "example.com/app/cmd/frontend",
+                &["example.com/app/internal/routes"],
+            ),
+        ];
+        let deps = [
+            imports("example.com/app/internal/queue", &["fmt"]),
+            imports(
+                "example.com/app/internal/routes",
+                &["example.com/app/internal/server"],
+            ),
+            imports("example.com/app/internal/server", &["net/http"]),
+            // HTTP imports of other modules don't count.
+            imports("github.com/aws/aws-sdk-go", &["net/http"]),
+        ];
+        let web = choose(&packages, &deps, Some("example.com/app")).unwrap();
+        assert_eq!(
+            web,
+            WebPackage {
+                package: String::from("example.com/app/cmd/frontend"),
+                reasons: vec![String::from(
+                    "imports net/http through example.com/app/internal/server"
+                )],
+            }
+        );
+
+        // Without a module path, only direct imports count.
+        let web = choose(&packages, &deps, None).unwrap();
+        assert_eq!(web.package, "example.com/app/cmd/worker");
+    }
+
+    #[test]
+    fn apply_process_settings_sets_working_dir() {
+        let pkgs = [
+            String::from("example.com/app/web"),
+            String::from("example.com/app/worker"),
+        ];
+        let mut procs = build_procs(&pkgs, None).unwrap();
+        apply_process_settings(
+            &mut procs,
+            &[
+                ProcessSettings {
+                    name: String::from("worker"),
+                    env: vec![(String::from("GOMAXPROCS"), String::from("1"))],
+                    working_dir: Some(String::from("jobs")),
+                },
+                ProcessSettings {
+                    name: String::from("web"),
+                    env: vec![(String::from("PORT"), String::from("8080"))],
+                    working_dir: None,
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(procs[0].working_directory, WorkingDirectory::App);
+        assert_eq!(
+            procs[1].working_directory,
+            WorkingDirectory::Directory(PathBuf::from("jobs"))
+        );
+    }
+
+    #[test]
+    fn apply_process_settings_unknown_process() {
+        let mut procs = build_procs(&[String::from("example.com/app/web")], None).unwrap();
+        let err = apply_process_settings(
+            &mut procs,
+            &[ProcessSettings {
+                name: String::from("wroker"),
+                env: vec![],
+                working_dir: Some(String::from("jobs")),
+            }],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Settings are configured for process wroker, which isn't registered. Registered processes: web"
+        );
+    }
+
+    fn process_config(name: &str, binary: &str) -> ProcessConfig {
+        ProcessConfig {
+            name: name.to_string(),
+            binary: binary.to_string(),
+            args: vec![],
+            working_dir: None,
+            default: false,
+        }
+    }
+
+    #[test]
+    fn merge_procs_replaces_and_adds() {
+        let pkgs = [
+            String::from("example.com/app/cmd/server"),
+            String::from("example.com/app/cmd/worker"),
+        ];
+        let procs = build_procs(&pkgs, None).unwrap();
+        let mut web = process_config("web", "server");
+        web.args = vec![String::from("-port"), String::from("80")];
+        let mut urgent = process_config("urgent", "worker");
+        urgent.working_dir = Some(String::from("jobs"));
+
+        let procs = merge_procs(procs, &pkgs, &[web, urgent]).unwrap();
+        let summary = procs
+            .iter()
+            .map(|p| {
+                (
+                    p.r#type.to_string(),
+                    p.command.join(" "),
+                    p.args.join(" "),
+                    p.default,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (
+                    String::from("server"),
+                    String::from("server"),
+                    String::new(),
+                    false
+                ),
+                (
+                    String::from("worker"),
+                    String::from("worker"),
+                    String::new(),
+                    false
+                ),
+                (
+                    String::from("web"),
+                    String::from("server"),
+                    String::from("-port 80"),
+                    true
+                ),
+                (
+                    String::from("urgent"),
+                    String::from("worker"),
+                    String::new(),
+                    false
+                ),
+            ]
+        );
+        assert_eq!(
+            procs[3].working_directory,
+            WorkingDirectory::Directory(PathBuf::from("jobs"))
+        );
+    }
+
+    #[test]
+    fn merge_procs_explicit_default() {
+        let pkgs = [
+            String::from("example.com/app/web"),
+            String::from("example.com/app/worker"),
+        ];
+        let procs = build_procs(&pkgs, None).unwrap();
+        let mut worker = process_config("worker", "worker");
+        worker.default = true;
+
+        let procs = merge_procs(procs, &pkgs, &[worker]).unwrap();
+        let defaults = procs
+            .iter()
+            .filter(|p| p.default)
+            .map(|p| p.r#type.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(defaults, ["worker"]);
+    }
+
+    #[test]
+    fn merge_procs_unknown_binary() {
+        let pkgs = [String::from("example.com/app/server")];
+        let err = merge_procs(vec![], &pkgs, &[process_config("web", "serverr")]).unwrap_err();
         assert_eq!(
             format!("{err}"),
-            "Invalid CNB process name: Invalid Value: []"
+            "Process web runs serverr, which is not a built binary. Built binaries: server"
+        );
+    }
+
+    #[test]
+    fn merge_procs_invalid_process() {
+        let pkgs = [String::from("example.com/app/server")];
+        let err =
+            merge_procs(vec![], &pkgs, &[process_config("web server", "server")]).unwrap_err();
+        assert_eq!(
+            format!("{err}"),
+            "Invalid CNB process name: Invalid Value: web server"
+        );
+    }
+
+    #[test]
+    fn build_procs_single_element_pkg() {
+        let procs = build_procs(&[String::from("foobar")], None).unwrap();
+        assert_eq!(procs[0].r#type.to_string(), "foobar");
+        assert_eq!(procs[0].command, ["foobar"]);
+        assert_eq!(procs[1].r#type.to_string(), "web");
+    }
+
+    #[test]
+    fn build_procs_sanitizes_process_names() {
+        let procs = build_procs(&[String::from("example.com/[]")], None).unwrap();
+        assert_eq!(procs[0].r#type.to_string(), "proc");
+        assert_eq!(procs[0].command, ["[]"]);
+    }
+
+    #[test]
+    fn process_names_sanitized() {
+        let names = process_names(&[
+            String::from("example.com/app/cmd/MyServer"),
+            String::from("example.com/app/cmd/my server"),
+            String::from("example.com/other/myserver"),
+            String::from("example.com/app/cmd/[worker]"),
+            String::from("gopkg.in/yaml.v2"),
+            String::from("example.com/app/cmd/api_v2"),
+            String::from("example.com/app/cmd/my-server"),
+            String::from("example.com/app/cmd/job--runner-"),
+        ]);
+        let summary = names
+            .iter()
+            .map(|name| (name.binary.as_str(), name.name.as_str(), name.is_renamed()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("MyServer", "MyServer", false),
+                ("my server", "my-server", true),
+                ("myserver", "myserver", false),
+                ("[worker]", "worker", true),
+                ("yaml.v2", "yaml.v2", false),
+                ("api_v2", "api_v2", false),
+                ("my-server", "my-server-2", true),
+                ("job--runner-", "job--runner-", false),
+            ]
         );
     }
 }
@@ -0,0 +1,235 @@
+use libcnb::data::launch::{Process, ProcessBuilder, ProcessType, ProcessTypeError};
+use std::fs;
+use std::path::Path;
+
+/// The maximum edit distance between a command and a built binary name for
+/// the command to be considered a typo of that binary.
+const MAX_TYPO_DISTANCE: usize = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Couldn't read Procfile: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid Procfile entry on line {0}: {1}")]
+    Entry(usize, String),
+    #[error("Invalid CNB process name in Procfile: {0}")]
+    ProcessName(#[from] ProcessTypeError),
+}
+
+/// A `{name}: {command}` entry in a Procfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcfileEntry {
+    pub(crate) name: String,
+    pub(crate) command: String,
+}
+
+/// Read the entries of a Procfile. Blank lines and `#` comments are ignored.
+///
+/// # Errors
+///
+/// Will return an error when the file cannot be read, or a line isn't a
+/// `{name}: {command}` entry.
+pub(crate) fn read_procfile(path: &Path) -> Result<Vec<ProcfileEntry>, Error> {
+    parse_procfile(&fs::read_to_string(path)?)
+}
+
+fn parse_procfile(contents: &str) -> Result<Vec<ProcfileEntry>, Error> {
+    let mut entries = vec![];
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, command) = line
+            .split_once(':')
+            .map(|(name, command)| (name.trim(), command.trim()))
+            .filter(|(name, command)| !name.is_empty() && !command.is_empty())
+            .ok_or_else(|| Error::Entry(index + 1, line.to_string()))?;
+        entries.push(ProcfileEntry {
+            name: name.to_string(),
+            command: command.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Check that Procfile commands refer to built binaries. Returns a warning
+/// message for each command that looks like a reference to a binary that
+/// doesn't exist, such as a misspelled binary name, or a `bin/` path that
+/// isn't present in the app directory.
+pub(crate) fn check_binaries(
+    entries: &[ProcfileEntry],
+    binaries: &[String],
+    app_dir: &Path,
+) -> Vec<String> {
+    let mut warnings = vec![];
+    for entry in entries {
+        let Some(program) = entry
+            .command
+            .split_whitespace()
+            .find(|word| !word.contains('=') && *word != "exec")
+        else {
+            continue;
+        };
+        let name = program.rsplit('/').next().unwrap_or(program);
+        let is_bin_path = program.trim_start_matches("./").starts_with("bin/");
+
+        if is_bin_path && !app_dir.join(program).exists() {
+            let message = if binaries.iter().any(|binary| binary == name) {
+                format!(
+                    "Procfile process {} runs {program}, which doesn't exist in the app directory. \
+                    Built binaries are on the PATH, use `{name}` instead.",
+                    entry.name
+                )
+            } else {
+                format!(
+                    "Procfile process {} runs {program}, which doesn't exist in the app directory \
+                    and isn't a built binary.{}",
+                    entry.name,
+                    suggestion(name, binaries)
+                )
+            };
+            warnings.push(message);
+        } else if !is_bin_path
+            && !program.contains('/')
+            && !binaries.iter().any(|binary| binary == name)
+            && closest_binary(name, binaries).is_some()
+        {
+            warnings.push(format!(
+                "Procfile process {} runs {program}, which isn't a built binary.{}",
+                entry.name,
+                suggestion(name, binaries)
+            ));
+        }
+    }
+    warnings
+}
+
+/// Turns Procfile entries into a CNB process list. Commands are run with
+/// `bash -c`, and the `web` process is flagged as default.
+///
+/// # Errors
+///
+/// Procfile process names that don't satisfy CNB process naming conventions
+/// will error.
+pub(crate) fn build_procs(entries: &[ProcfileEntry]) -> Result<Vec<Process>, Error> {
+    entries
+        .iter()
+        .map(|entry| {
+            Ok(
+                ProcessBuilder::new(entry.name.parse::<ProcessType>()?, ["bash", "-c"])
+                    .arg(&entry.command)
+                    .default(entry.name == "web")
+                    .build(),
+            )
+        })
+        .collect()
+}
+
+fn suggestion(name: &str, binaries: &[String]) -> String {
+    closest_binary(name, binaries)
+        .map(|binary| format!(" Did you mean `{binary}`?"))
+        .unwrap_or_default()
+}
+
+fn closest_binary<'a>(name: &str, binaries: &'a [String]) -> Option<&'a String> {
+    binaries
+        .iter()
+        .map(|binary| (edit_distance(name, binary), binary))
+        .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, binary)| binary)
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_procfile_entries() {
+        let entries =
+            parse_procfile("# processes\nweb: server -port $PORT\n\nworker:worker\n").unwrap();
+        assert_eq!(
+            entries,
+            [
+                ProcfileEntry {
+                    name: "web".to_string(),
+                    command: "server -port $PORT".to_string(),
+                },
+                ProcfileEntry {
+                    name: "worker".to_string(),
+                    command: "worker".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_procfile_invalid_entry() {
+        let err = parse_procfile("web: server\nworker\n").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid Procfile entry on line 2: worker");
+    }
+
+    #[test]
+    fn check_binaries_warnings() {
+        let app_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(app_dir.path().join("bin")).unwrap();
+        fs::write(app_dir.path().join("bin/start"), "").unwrap();
+        let entries = parse_procfile(
+            "web: bin/serverr\n\
+            api: bin/server\n\
+            worker: GOMAXPROCS=1 exec wroker\n\
+            release: ./bin/start\n\
+            console: bash\n\
+            ok: server --debug\n",
+        )
+        .unwrap();
+        let binaries = ["server".to_string(), "worker".to_string()];
+
+        assert_eq!(
+            check_binaries(&entries, &binaries, app_dir.path()),
+            [
+                "Procfile process web runs bin/serverr, which doesn't exist in the app directory \
+                and isn't a built binary. Did you mean `server`?",
+                "Procfile process api runs bin/server, which doesn't exist in the app directory. \
+                Built binaries are on the PATH, use `server` instead.",
+                "Procfile process worker runs wroker, which isn't a built binary. Did you mean `worker`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_procs_from_procfile() {
+        let procs =
+            build_procs(&parse_procfile("web: server -port $PORT\nworker: worker\n").unwrap())
+                .unwrap();
+        assert_eq!(procs[0].r#type.to_string(), "web");
+        assert_eq!(procs[0].command, ["bash", "-c"]);
+        assert_eq!(procs[0].args, ["server -port $PORT"]);
+        assert!(procs[0].default);
+        assert!(!procs[1].default);
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("server", "server"), 0);
+        assert_eq!(edit_distance("serverr", "server"), 1);
+        assert_eq!(edit_distance("wroker", "worker"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
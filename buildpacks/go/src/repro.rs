@@ -0,0 +1,330 @@
+use bullet_stream::global::print;
+use bullet_stream::style;
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Flags passed to `go install` in reproducible mode: `-trimpath` removes
+/// file system paths (such as the app and layer directories) from binaries,
+/// and `-buildvcs=false` omits version control state, which may differ
+/// between checkouts of the same commit.
+const BUILD_FLAGS: [&str; 2] = ["-trimpath", "-buildvcs=false"];
+
+/// The linker flag for an empty build ID, which avoids embedding an ID that
+/// depends on the build environment.
+const BUILD_ID_LDFLAG: &str = "-buildid=";
+
+/// The default `SOURCE_DATE_EPOCH` (1980-01-01T00:00:01Z), which matches the
+/// timestamp the CNB lifecycle gives image layers.
+const DEFAULT_SOURCE_DATE_EPOCH: &str = "315532801";
+
+#[derive(thiserror::Error, Debug)]
+#[error("Couldn't compute reproducible build digests: {0}")]
+pub(crate) struct Error(#[from] std::io::Error);
+
+/// Digests recorded for a reproducible build: a fingerprint of the build
+/// inputs, and the SHA-256 digest of each built binary.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct BuildRecord {
+    pub(crate) fingerprint: String,
+    pub(crate) digests: BTreeMap<String, String>,
+}
+
+/// The result of comparing a build with the previous build.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    /// The build inputs changed, so the binaries aren't comparable.
+    InputsChanged,
+    /// The build inputs and all binaries are identical.
+    Identical,
+    /// The build inputs are identical, but these binaries differ.
+    Drift(Vec<String>),
+}
+
+/// Configure the build environment for reproducible builds. The toolchain is
+/// pinned to the installed version (instead of any `toolchain` directive),
+/// and `SOURCE_DATE_EPOCH`, which cgo compilers use for timestamps, is set
+/// if it isn't already.
+pub(crate) fn build_env(go_env: &Env) -> Env {
+    let mut env = go_env.clone();
+    env.insert("GOTOOLCHAIN", "local");
+    if env.get("SOURCE_DATE_EPOCH").is_none() {
+        env.insert("SOURCE_DATE_EPOCH", DEFAULT_SOURCE_DATE_EPOCH);
+    }
+    for key in ["GOTOOLCHAIN", "SOURCE_DATE_EPOCH"] {
+        if let Some(value) = env.get_string_lossy(key) {
+            print::sub_bullet(format!(
+                "Setting {}",
+                style::value(format!("{key}={value}"))
+            ));
+        }
+    }
+    env
+}
+
+/// The `go install` flags for reproducible builds. An `-ldflags` flag on the
+/// command line replaces any in `GOFLAGS`, so the last one there is merged
+/// into it.
+pub(crate) fn build_flags(go_env: &Env) -> Vec<String> {
+    let user_ldflags = go_env.get_string_lossy("GOFLAGS").and_then(|goflags| {
+        goflags.split_whitespace().rev().find_map(|flag| {
+            flag.strip_prefix("--")
+                .or_else(|| flag.strip_prefix('-'))?
+                .strip_prefix("ldflags=")
+                .map(ToString::to_string)
+        })
+    });
+    let ldflags = match &user_ldflags {
+        Some(ldflags) => {
+            print::sub_bullet(format!(
+                "Merging {} from {}",
+                style::value(format!("-ldflags={ldflags}")),
+                style::value("GOFLAGS")
+            ));
+            format!("-ldflags={ldflags} {BUILD_ID_LDFLAG}")
+        }
+        None => format!("-ldflags={BUILD_ID_LDFLAG}"),
+    };
+    let mut flags = BUILD_FLAGS.map(String::from).to_vec();
+    flags.push(ldflags);
+    print::sub_bullet(format!(
+        "Using build flags: {}",
+        style::value(flags.join(" "))
+    ));
+    flags
+}
+
+/// Record the digests of the binaries built from an app directory. The
+/// fingerprint covers the Go version, build settings (flags and build
+/// environment variables, like `GOAMD64`), and the path and contents of
+/// `go.mod`, `go.sum`, and the given build inputs, which are the source files
+/// of packages in the app directory. Other dependencies are covered by the
+/// `go.sum` hashes.
+///
+/// # Errors
+///
+/// Will return an error when a build input or the binary directory cannot be
+/// read.
+pub(crate) fn record_build(
+    app_dir: &Path,
+    inputs: &[PathBuf],
+    bin_dir: &Path,
+    go_version: &str,
+    build_settings: &BTreeMap<String, String>,
+) -> Result<BuildRecord, Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(go_version);
+    hasher.update([0]);
+    for (key, value) in build_settings {
+        hasher.update(format!("{key}={value}\n"));
+    }
+    hasher.update([0]);
+    let mut paths = inputs
+        .iter()
+        .filter(|path| path.starts_with(app_dir))
+        .cloned()
+        .chain(
+            ["go.mod", "go.sum"]
+                .map(|name| app_dir.join(name))
+                .into_iter()
+                .filter(|path| path.exists()),
+        )
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    for path in paths {
+        hasher.update(
+            path.strip_prefix(app_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .as_bytes(),
+        );
+        hasher.update([0]);
+        io::copy(&mut fs::File::open(&path)?, &mut HashWriter(&mut hasher))?;
+        hasher.update([0]);
+    }
+
+    let mut digests = BTreeMap::new();
+    for entry in fs::read_dir(bin_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let mut hasher = Sha256::new();
+            io::copy(
+                &mut fs::File::open(entry.path())?,
+                &mut HashWriter(&mut hasher),
+            )?;
+            digests.insert(
+                entry.file_name().to_string_lossy().to_string(),
+                hex::encode(hasher.finalize()),
+            );
+        }
+    }
+    Ok(BuildRecord {
+        fingerprint: hex::encode(hasher.finalize()),
+        digests,
+    })
+}
+
+/// Streams written bytes into a hasher, so files don't have to be read into
+/// memory to hash them.
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Compare a build with the previous build.
+pub(crate) fn compare(previous: &BuildRecord, current: &BuildRecord) -> Comparison {
+    if previous.fingerprint != current.fingerprint {
+        return Comparison::InputsChanged;
+    }
+    let drifted = current
+        .digests
+        .iter()
+        .filter(|(binary, digest)| previous.digests.get(*binary) != Some(digest))
+        .map(|(binary, _)| binary.clone())
+        .collect::<Vec<_>>();
+    if drifted.is_empty() && previous.digests.len() == current.digests.len() {
+        Comparison::Identical
+    } else {
+        Comparison::Drift(drifted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record a build, with the `.go` files of the app as build inputs.
+    fn build(app_files: &[(&str, &str)], binaries: &[(&str, &str)]) -> BuildRecord {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("app");
+        let bin_dir = dir.path().join("bin");
+        let mut inputs = vec![];
+        for (path, contents) in app_files {
+            let path = app_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            if path.extension().is_some_and(|ext| ext == "go") {
+                inputs.push(path);
+            }
+        }
+        // Files outside the app directory, like the standard library, are
+        // covered by the Go version.
+        inputs.push(PathBuf::from("/usr/local/go/src/fmt/print.go"));
+        fs::create_dir_all(&bin_dir).unwrap();
+        for (name, contents) in binaries {
+            fs::write(bin_dir.join(name), contents).unwrap();
+        }
+        let settings = BTreeMap::from([(String::from("flags"), String::from("-trimpath"))]);
+        record_build(&app_dir, &inputs, &bin_dir, "go1.22.1", &settings).unwrap()
+    }
+
+    #[test]
+    fn record_build_digests() {
+        let record = build(&[("main.go", "package main")], &[("server", "binary")]);
+        assert_eq!(
+            record.digests,
+            BTreeMap::from([(
+                String::from("server"),
+                String::from("9a3a45d01531a20e89ac6ae10b0b0beb0492acd7216a368aa062d1a5fecaf9cd")
+            )])
+        );
+    }
+
+    #[test]
+    fn compare_builds() {
+        let app = [("main.go", "package main"), (".git/HEAD", "ref: main")];
+        let previous = build(&app, &[("server", "binary")]);
+
+        assert_eq!(
+            compare(&previous, &build(&app, &[("server", "binary")])),
+            Comparison::Identical
+        );
+        assert_eq!(
+            compare(
+                &previous,
+                &build(
+                    &[("main.go", "package main"), (".git/HEAD", "ref: other")],
+                    &[("server", "binary")]
+                )
+            ),
+            Comparison::Identical
+        );
+        assert_eq!(
+            compare(&previous, &build(&app, &[("server", "different")])),
+            Comparison::Drift(vec![String::from("server")])
+        );
+        assert_eq!(
+            compare(
+                &previous,
+                &build(
+                    &[("main.go", "package main // changed")],
+                    &[("server", "different")]
+                )
+            ),
+            Comparison::InputsChanged
+        );
+    }
+
+    #[test]
+    fn fingerprint_build_inputs() {
+        let app = [
+            ("go.mod", "module example.com/app"),
+            ("main.go", "package main"),
+            ("README.md", "# App"),
+            ("vendor/example.com/dep/dep.go", "package dep"),
+        ];
+        let previous = build(&app, &[("server", "binary")]);
+
+        let mut changed = app;
+        changed[2].1 = "# App, documented";
+        assert_eq!(
+            build(&changed, &[("server", "binary")]).fingerprint,
+            previous.fingerprint
+        );
+        for (index, contents) in [
+            (0, "module example.com/app\n\ngo 1.22"),
+            (3, "package dep // changed"),
+        ] {
+            let mut changed = app;
+            changed[index].1 = contents;
+            assert_ne!(
+                build(&changed, &[("server", "binary")]).fingerprint,
+                previous.fingerprint
+            );
+        }
+        let mut with_gosum = app.to_vec();
+        with_gosum.push(("go.sum", "example.com/dep v1.0.0 h1:abc="));
+        assert_ne!(
+            build(&with_gosum, &[("server", "binary")]).fingerprint,
+            previous.fingerprint
+        );
+    }
+
+    #[test]
+    fn reproducible_build_flags() {
+        assert_eq!(
+            build_flags(&Env::new()),
+            ["-trimpath", "-buildvcs=false", "-ldflags=-buildid="]
+        );
+
+        let mut env = Env::new();
+        env.insert("GOFLAGS", "-mod=vendor -ldflags=-s --ldflags=-w");
+        assert_eq!(
+            build_flags(&env),
+            ["-trimpath", "-buildvcs=false", "-ldflags=-w -buildid="]
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use crate::buildinfo::{BuildInfo, Module};
+use heroku_go_utils::vrs::GoVersion;
+use libcnb::data::sbom::SbomFormat;
+use libcnb::sbom::Sbom;
+use libherokubuildpack::inventory::artifact::Artifact;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const TOOL_NAME: &str = "heroku/go";
+
+// The CNB lifecycle sets image timestamps to this same fixed date, so that
+// builds of the same source produce the same image.
+const CREATED: &str = "1980-01-01T00:00:01Z";
+
+/// The contents of an SBOM: the Go toolchain used for the build, each built
+/// binary, and the modules embedded in them, by module ID.
+struct Inventory<'a> {
+    toolchain: &'a Artifact<GoVersion, Sha256, Option<()>>,
+    binaries: Vec<Binary<'a>>,
+    modules: BTreeMap<String, &'a Module>,
+    module_spdx_ids: BTreeMap<String, String>,
+}
+
+struct Binary<'a> {
+    name: String,
+    info: &'a BuildInfo,
+    modules: Vec<String>,
+}
+
+/// Build `CycloneDX` and SPDX SBOMs for binaries built with a Go toolchain,
+/// from their `go version -m` build information.
+pub(crate) fn build_sboms(
+    infos: &[BuildInfo],
+    toolchain: &Artifact<GoVersion, Sha256, Option<()>>,
+    tool_version: &str,
+) -> Vec<Sbom> {
+    let inventory = Inventory::new(infos, toolchain);
+    vec![
+        Sbom::from_bytes(
+            SbomFormat::CycloneDxJson,
+            cyclonedx(&inventory, tool_version).to_string(),
+        ),
+        Sbom::from_bytes(
+            SbomFormat::SpdxJson,
+            spdx(&inventory, tool_version).to_string(),
+        ),
+    ]
+}
+
+impl<'a> Inventory<'a> {
+    fn new(infos: &'a [BuildInfo], toolchain: &'a Artifact<GoVersion, Sha256, Option<()>>) -> Self {
+        let mut modules = BTreeMap::new();
+        let binaries = infos
+            .iter()
+            .map(|info| Binary {
+                name: Path::new(&info.binary).file_name().map_or_else(
+                    || info.binary.clone(),
+                    |name| name.to_string_lossy().to_string(),
+                ),
+                info,
+                modules: info
+                    .deps
+                    .iter()
+                    .map(|dep| {
+                        let id = module_id(identity(dep));
+                        modules.insert(id.clone(), dep);
+                        id
+                    })
+                    .collect(),
+            })
+            .collect();
+        Inventory {
+            toolchain,
+            binaries,
+            module_spdx_ids: module_spdx_ids(modules.keys()),
+            modules,
+        }
+    }
+
+    fn module_spdx_id(&self, module_id: &str) -> String {
+        self.module_spdx_ids
+            .get(module_id)
+            .cloned()
+            .unwrap_or_else(|| spdx_id("module", module_id))
+    }
+
+    fn toolchain_version(&self) -> String {
+        self.toolchain
+            .version
+            .to_string()
+            .trim_start_matches("go")
+            .to_string()
+    }
+
+    fn toolchain_checksum(&self) -> String {
+        hex::encode(&self.toolchain.checksum.value)
+    }
+}
+
+fn cyclonedx(inventory: &Inventory, tool_version: &str) -> Value {
+    let toolchain_version = inventory.toolchain_version();
+    let mut components = vec![json!({
+        "type": "application",
+        "bom-ref": "go-toolchain",
+        "name": "go",
+        "version": toolchain_version,
+        "purl": format!("pkg:golang/stdlib@{toolchain_version}"),
+        "hashes": [{ "alg": "SHA-256", "content": inventory.toolchain_checksum() }],
+        "externalReferences": [{ "type": "distribution", "url": inventory.toolchain.url }],
+    })];
+    let mut dependencies = vec![];
+
+    for binary in &inventory.binaries {
+        let bom_ref = format!("binary:{}", binary.name);
+        let mut component = json!({
+            "type": "application",
+            "bom-ref": bom_ref,
+            "name": binary.name,
+            "properties": build_properties(binary.info),
+        });
+        if let Some(main) = &binary.info.main {
+            component["version"] = json!(main.version);
+            if let Some(purl) = purl(main) {
+                component["purl"] = json!(purl);
+            }
+        }
+        components.push(component);
+        let mut depends_on = binary.modules.clone();
+        depends_on.push(String::from("go-toolchain"));
+        dependencies.push(json!({ "ref": bom_ref, "dependsOn": depends_on }));
+    }
+
+    for (id, dep) in &inventory.modules {
+        let module = identity(dep);
+        let mut component = json!({
+            "type": "library",
+            "bom-ref": id,
+            "name": module.path,
+            "version": module.version,
+        });
+        if let Some(purl) = purl(module) {
+            component["purl"] = json!(purl);
+        }
+        let mut properties = vec![];
+        if let Some(sum) = &module.sum {
+            properties.push(json!({ "name": "go:module:sum", "value": sum }));
+        }
+        if let Some(replacement) = local_replacement(dep) {
+            properties.push(json!({ "name": "go:module:replace", "value": replacement.path }));
+        }
+        if !properties.is_empty() {
+            component["properties"] = json!(properties);
+        }
+        components.push(component);
+    }
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": CREATED,
+            "tools": {
+                "components": [{ "type": "application", "name": TOOL_NAME, "version": tool_version }],
+            },
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+fn spdx(inventory: &Inventory, tool_version: &str) -> Value {
+    let toolchain_version = inventory.toolchain_version();
+    let toolchain_id = "SPDXRef-toolchain-go";
+    let mut packages = vec![json!({
+        "SPDXID": toolchain_id,
+        "name": "go",
+        "versionInfo": toolchain_version,
+        "downloadLocation": inventory.toolchain.url,
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "copyrightText": "NOASSERTION",
+        "checksums": [{ "algorithm": "SHA256", "checksumValue": inventory.toolchain_checksum() }],
+        "externalRefs": [purl_ref(&format!("pkg:golang/stdlib@{toolchain_version}"))],
+    })];
+    let mut relationships = vec![];
+
+    for binary in &inventory.binaries {
+        let binary_id = spdx_id("binary", &binary.name);
+        let mut package = json!({
+            "SPDXID": binary_id,
+            "name": binary.name,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": "NOASSERTION",
+            "copyrightText": "NOASSERTION",
+            "primaryPackagePurpose": "APPLICATION",
+            "comment": format!("Build settings: {}", build_settings(binary.info)),
+        });
+        if let Some(main) = &binary.info.main {
+            package["versionInfo"] = json!(main.version);
+            if let Some(purl) = purl(main) {
+                package["externalRefs"] = json!([purl_ref(&purl)]);
+            }
+        }
+        packages.push(package);
+        relationships.push(relationship("SPDXRef-DOCUMENT", "DESCRIBES", &binary_id));
+        relationships.push(relationship(toolchain_id, "BUILD_TOOL_OF", &binary_id));
+        for module in &binary.modules {
+            relationships.push(relationship(
+                &binary_id,
+                "DEPENDS_ON",
+                &inventory.module_spdx_id(module),
+            ));
+        }
+    }
+
+    for (id, dep) in &inventory.modules {
+        let module = identity(dep);
+        let mut package = json!({
+            "SPDXID": inventory.module_spdx_id(id),
+            "name": module.path,
+            "versionInfo": module.version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": "NOASSERTION",
+            "copyrightText": "NOASSERTION",
+            "primaryPackagePurpose": "LIBRARY",
+        });
+        if let Some(purl) = purl(module) {
+            package["externalRefs"] = json!([purl_ref(&purl)]);
+        }
+        if let Some(replacement) = local_replacement(dep) {
+            package["comment"] = json!(format!("Replaced by {}", replacement.path));
+        }
+        packages.push(package);
+    }
+
+    // The namespace must be unique per document, so it's derived from the
+    // contents, which keeps it stable across identical builds.
+    let namespace = hex::encode(Sha256::digest(
+        json!([packages, relationships]).to_string().as_bytes(),
+    ));
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": "go_target",
+        "documentNamespace": format!("https://github.com/heroku/buildpacks-go/sbom/{namespace}"),
+        "creationInfo": {
+            "created": CREATED,
+            "creators": [format!("Tool: {TOOL_NAME}-{tool_version}")],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// The module that identifies an embedded module in an SBOM: the module
+/// that was built, except for replacements with a local directory, which
+/// are identified by the module they replace.
+fn identity(dep: &Module) -> &Module {
+    if local_replacement(dep).is_some() {
+        dep
+    } else {
+        dep.built()
+    }
+}
+
+/// The replacement of a module with a local directory, if any.
+fn local_replacement(dep: &Module) -> Option<&Module> {
+    dep.replace
+        .as_deref()
+        .filter(|replacement| purl(replacement).is_none())
+}
+
+/// A unique identifier for a module: its package URL, or its path and
+/// version for modules without one.
+fn module_id(module: &Module) -> String {
+    purl(module).unwrap_or_else(|| format!("{}@{}", module.path, module.version))
+}
+
+/// The package URL for a module. Modules built from source, such as the main
+/// module, have a `(devel)` version, so their package URL has no version.
+/// Replacements with a directory aren't addressable, so there's no package
+/// URL.
+fn purl(module: &Module) -> Option<String> {
+    if module.path.starts_with('.') || module.path.starts_with('/') {
+        return None;
+    }
+    if module.version == "(devel)" {
+        Some(format!("pkg:golang/{}", module.path))
+    } else {
+        Some(format!("pkg:golang/{}@{}", module.path, module.version))
+    }
+}
+
+fn purl_ref(purl: &str) -> Value {
+    json!({
+        "referenceCategory": "PACKAGE-MANAGER",
+        "referenceType": "purl",
+        "referenceLocator": purl,
+    })
+}
+
+fn relationship(element: &str, relationship_type: &str, related: &str) -> Value {
+    json!({
+        "spdxElementId": element,
+        "relationshipType": relationship_type,
+        "relatedSpdxElement": related,
+    })
+}
+
+/// An SPDX identifier, which may only contain letters, numbers, `.` and `-`.
+fn spdx_id(kind: &str, name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    format!("SPDXRef-{kind}-{name}")
+}
+
+/// The SPDX identifiers of modules, by module ID. Module IDs that are only
+/// distinct in characters SPDX identifiers can't contain, like `a/b` and
+/// `a-b`, get a suffix from a hash of their module ID.
+fn module_spdx_ids<'a>(module_ids: impl Iterator<Item = &'a String>) -> BTreeMap<String, String> {
+    let mut by_spdx_id = BTreeMap::<String, Vec<&String>>::new();
+    for module_id in module_ids {
+        by_spdx_id
+            .entry(spdx_id(
+                "module",
+                module_id.trim_start_matches("pkg:golang/"),
+            ))
+            .or_default()
+            .push(module_id);
+    }
+    let mut spdx_ids = BTreeMap::new();
+    for (id, module_ids) in by_spdx_id {
+        let colliding = module_ids.len() > 1;
+        for module_id in module_ids {
+            let id = if colliding {
+                let hash = hex::encode(Sha256::digest(module_id.as_bytes()));
+                format!("{id}-{}", &hash[..8])
+            } else {
+                id.clone()
+            };
+            spdx_ids.insert(module_id.clone(), id);
+        }
+    }
+    spdx_ids
+}
+
+fn build_properties(info: &BuildInfo) -> Value {
+    let mut properties = vec![json!({ "name": "go:version", "value": info.go_version })];
+    properties.extend(
+        info.settings
+            .iter()
+            .map(|(key, value)| json!({ "name": format!("go:build:{key}"), "value": value })),
+    );
+    json!(properties)
+}
+
+fn build_settings(info: &BuildInfo) -> String {
+    info.settings
+        .iter()
+        .map(|(key, value)| {
+            if value.is_empty() {
+                key.clone()
+            } else {
+                format!("{key}={value}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildinfo::{GO_VERSION_M, parse_go_version_m};
+    use crate::inv::GoInventory;
+    use libcnb::data::sbom::SbomFormat;
+
+    fn toolchain() -> Artifact<GoVersion, Sha256, Option<()>> {
+        "[[artifacts]]
+        version = \"go1.22.1\"
+        os = \"linux\"
+        arch = \"amd64\"
+        url = \"https://go.dev/dl/go1.22.1.linux-amd64.tar.gz\"
+        checksum = \"sha256:aab8e15785c997ae20f9c88422ee35d962c4562212bb0f879d052a35c8307c7f\"
+        "
+        .parse::<GoInventory>()
+        .unwrap()
+        .artifacts
+        .remove(0)
+    }
+
+    fn sbom_json(sboms: &[Sbom], format: &SbomFormat) -> Value {
+        let sbom = sboms.iter().find(|sbom| &sbom.format == format).unwrap();
+        serde_json::from_slice(&sbom.data).unwrap()
+    }
+
+    #[test]
+    fn cyclonedx_sbom() {
+        let infos = parse_go_version_m(GO_VERSION_M);
+        let sbom = sbom_json(
+            &build_sboms(&infos, &toolchain(), "1.0.0"),
+            &SbomFormat::CycloneDxJson,
+        );
+
+        let components = sbom["components"].as_array().unwrap();
+        let names = components
+            .iter()
+            .map(|c| {
+                (
+                    c["name"].as_str().unwrap(),
+                    c["purl"].as_str().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("go", "pkg:golang/stdlib@1.22.1"),
+                ("server", "pkg:golang/example.com/app"),
+                ("worker", "pkg:golang/example.com/app"),
+                (
+                    "github.com/gin-gonic/gin",
+                    "pkg:golang/github.com/gin-gonic/gin@v1.9.1"
+                ),
+                (
+                    "github.com/pkg/errors",
+                    "pkg:golang/github.com/pkg/errors@v0.9.1"
+                ),
+            ]
+        );
+        assert_eq!(
+            components[4]["properties"],
+            json!([{ "name": "go:module:replace", "value": "../errors" }])
+        );
+        assert_eq!(
+            components[0]["hashes"][0]["content"],
+            "aab8e15785c997ae20f9c88422ee35d962c4562212bb0f879d052a35c8307c7f"
+        );
+        assert_eq!(
+            components[1]["properties"][3],
+            json!({ "name": "go:build:CGO_ENABLED", "value": "1" })
+        );
+        assert_eq!(
+            sbom["dependencies"][0]["dependsOn"],
+            json!([
+                "pkg:golang/github.com/gin-gonic/gin@v1.9.1",
+                "pkg:golang/github.com/pkg/errors@v0.9.1",
+                "go-toolchain"
+            ])
+        );
+    }
+
+    #[test]
+    fn spdx_sbom() {
+        let infos = parse_go_version_m(GO_VERSION_M);
+        let sboms = build_sboms(&infos, &toolchain(), "1.0.0");
+        let sbom = sbom_json(&sboms, &SbomFormat::SpdxJson);
+
+        assert_eq!(sbom["spdxVersion"], "SPDX-2.3");
+        assert_eq!(
+            sbom["packages"][1]["comment"],
+            "Build settings: -buildmode=exe, -trimpath, CGO_ENABLED=1, vcs.revision=abc123"
+        );
+        let package_ids = sbom["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["SPDXID"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            package_ids,
+            [
+                "SPDXRef-toolchain-go",
+                "SPDXRef-binary-server",
+                "SPDXRef-binary-worker",
+                "SPDXRef-module-github.com-gin-gonic-gin-v1.9.1",
+                "SPDXRef-module-github.com-pkg-errors-v0.9.1",
+            ]
+        );
+        assert_eq!(sbom["packages"][4]["comment"], "Replaced by ../errors");
+        assert_eq!(
+            sbom["relationships"][1],
+            json!({
+                "spdxElementId": "SPDXRef-toolchain-go",
+                "relationshipType": "BUILD_TOOL_OF",
+                "relatedSpdxElement": "SPDXRef-binary-server",
+            })
+        );
+        // Identical builds produce identical documents.
+        assert_eq!(
+            sboms[1].data,
+            build_sboms(&infos, &toolchain(), "1.0.0")[1].data
+        );
+    }
+
+    #[test]
+    fn module_purl() {
+        let module = |path: &str, version: &str| Module {
+            path: path.to_string(),
+            version: version.to_string(),
+            sum: None,
+            replace: None,
+        };
+        assert_eq!(
+            purl(&module("github.com/pkg/errors", "v0.9.1")).as_deref(),
+            Some("pkg:golang/github.com/pkg/errors@v0.9.1")
+        );
+        assert_eq!(
+            purl(&module("example.com/app", "(devel)")).as_deref(),
+            Some("pkg:golang/example.com/app")
+        );
+        assert_eq!(purl(&module("../errors", "(devel)")), None);
+        assert_eq!(purl(&module("/src/errors", "")), None);
+    }
+
+    #[test]
+    fn spdx_sbom_colliding_module_ids() {
+        let infos = parse_go_version_m(
+            "\
+/layers/heroku_go/go_target/bin/server: go1.22.1
+\tdep\texample.com/a/b\tv1.0.0
+\tdep\texample.com/a-b\tv1.0.0
+\tdep\texample.com/c\tv1.0.0
+",
+        );
+        let sbom = sbom_json(
+            &build_sboms(&infos, &toolchain(), "1.0.0"),
+            &SbomFormat::SpdxJson,
+        );
+        let module_ids = sbom["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["SPDXID"].as_str().unwrap())
+            .filter(|id| id.starts_with("SPDXRef-module-"))
+            .collect::<Vec<_>>();
+        assert_eq!(module_ids.len(), 3);
+        assert!(module_ids[0].starts_with("SPDXRef-module-example.com-a-b-v1.0.0-"));
+        assert!(module_ids[1].starts_with("SPDXRef-module-example.com-a-b-v1.0.0-"));
+        assert_ne!(module_ids[0], module_ids[1]);
+        assert_eq!(module_ids[2], "SPDXRef-module-example.com-c-v1.0.0");
+        let related = sbom["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|r| r["relationshipType"] == "DEPENDS_ON")
+            .map(|r| r["relatedSpdxElement"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        // Relationships follow the order of dependencies, and packages the
+        // order of module IDs, where `example.com/a-b` comes first.
+        assert_eq!(related, [module_ids[1], module_ids[0], module_ids[2]]);
+    }
+}
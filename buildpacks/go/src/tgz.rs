@@ -3,7 +3,8 @@ use libherokubuildpack::inventory::artifact::Artifact;
 use retry::retry;
 use retry::{OperationResult, delay::Exponential};
 use sha2::Digest;
-use std::{fs, io::Read, path::StripPrefixError, time::Duration};
+use std::path::{Path, StripPrefixError};
+use std::{fs, io::Read, time::Duration};
 use tar::Archive;
 use tracing::instrument;
 use ureq::Body;
@@ -36,10 +37,22 @@ pub(crate) enum Error {
     Prefix(StripPrefixError),
 }
 
+/// The sizes of the files extracted from an archive, and of the files that
+/// were left out by exclude patterns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Extracted {
+    pub(crate) bytes: u64,
+    pub(crate) excluded_bytes: u64,
+}
+
 /// Fetches a tarball from the artifact url, strips component paths, filters path
-/// prefixes, extracts files to a directory, and verifies the artifact checksum. Care
-/// is taken not to write temporary files or read the entire contents into memory.
-/// In an error scenario, any archive contents already extracted will not be removed.
+/// prefixes and exclude patterns, extracts files to a directory, and verifies the
+/// artifact checksum. Care is taken not to write temporary files or read the entire
+/// contents into memory. In an error scenario, any archive contents already
+/// extracted will not be removed.
+///
+/// Exclude patterns are matched against the stripped path of each entry (see
+/// `is_excluded`).
 ///
 /// # Errors
 ///
@@ -53,35 +66,44 @@ pub(crate) fn fetch_strip_filter_extract_verify<
     artifact: &Artifact<V, D, Option<()>>,
     strip_prefix: impl AsRef<str> + std::fmt::Debug,
     filter_prefixes: impl Iterator<Item = &'a str> + std::fmt::Debug,
+    exclude_patterns: &[&str],
     dest_dir: impl AsRef<std::path::Path> + std::fmt::Debug,
-) -> Result<(), Error> {
+) -> Result<Extracted, Error> {
     let destination = dest_dir.as_ref();
     let mut res = download_result(&artifact.url)?;
     let body = res.body_mut().as_reader();
 
     let mut archive = Archive::new(GzDecoder::new(DigestingReader::new(body, D::new())));
     let filters: Vec<&str> = filter_prefixes.into_iter().collect();
+    let mut extracted = Extracted::default();
     for entry in archive.entries().map_err(Error::Entries)? {
         let mut file = entry.map_err(Error::Entry)?;
-        let path = destination.join(
-            file.path()
-                .map_err(Error::Path)?
-                .strip_prefix(strip_prefix.as_ref())
-                .map_err(Error::Prefix)?,
-        );
-        if filters
+        let relative_path = file
+            .path()
+            .map_err(Error::Path)?
+            .strip_prefix(strip_prefix.as_ref())
+            .map_err(Error::Prefix)?
+            .to_path_buf();
+        if !filters
             .iter()
-            .any(|prefix| path.starts_with(destination.join(prefix)))
+            .any(|prefix| relative_path.starts_with(prefix))
         {
-            if let Some(parent) = path.parent() {
-                fs::create_dir_all(parent).map_err(Error::Directory)?;
-            }
-            file.unpack(&path).map_err(Error::Unpack)?;
+            continue;
         }
+        if is_excluded(&relative_path, exclude_patterns) {
+            extracted.excluded_bytes += file.size();
+            continue;
+        }
+        let path = destination.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(Error::Directory)?;
+        }
+        extracted.bytes += file.size();
+        file.unpack(&path).map_err(Error::Unpack)?;
     }
     let actual_digest = archive.into_inner().into_inner().finalize();
     (actual_digest.to_vec() == artifact.checksum.value)
-        .then_some(())
+        .then_some(extracted)
         .ok_or_else(|| {
             Error::Checksum(
                 hex::encode(artifact.checksum.value.clone()),
@@ -90,6 +112,64 @@ pub(crate) fn fetch_strip_filter_extract_verify<
         })
 }
 
+/// Whether a relative path is excluded by a list of patterns. Patterns are
+/// `/` separated, where `*` matches any characters within a path component,
+/// and a `**` component matches any number of components. A pattern that
+/// matches a directory also matches everything inside it. Patterns starting
+/// with `!` include paths excluded by earlier patterns, and the last
+/// matching pattern wins.
+fn is_excluded(path: &Path, patterns: &[&str]) -> bool {
+    let components = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    let components = components.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+    let mut excluded = false;
+    for pattern in patterns {
+        let (included, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, *pattern),
+        };
+        if matches_components(&pattern.split('/').collect::<Vec<_>>(), &components) {
+            excluded = !included;
+        }
+    }
+    excluded
+}
+
+fn matches_components(pattern: &[&str], components: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((&"**", rest)) => {
+            (0..=components.len()).any(|skip| matches_components(rest, &components[skip..]))
+        }
+        Some((first, rest)) => components
+            .split_first()
+            .is_some_and(|(component, components)| {
+                matches_component(first, component) && matches_components(rest, components)
+            }),
+    }
+}
+
+fn matches_component(pattern: &str, component: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == component,
+        Some((prefix, rest)) => component.strip_prefix(prefix).is_some_and(|remaining| {
+            remaining
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain([remaining.len()])
+                .any(|index| matches_component(rest, &remaining[index..]))
+        }),
+    }
+}
+
+/// Format a size in bytes as mebibytes, with one decimal place.
+pub(crate) fn format_size(bytes: u64) -> String {
+    let tenths = bytes * 10 / (1024 * 1024);
+    format!("{}.{} MiB", tenths / 10, tenths % 10)
+}
+
 const MAX_RETRIES: usize = 4;
 const INITIAL_DELAY: Duration = Duration::from_secs(1);
 
@@ -163,12 +243,44 @@ mod tests {
         fetch_strip_filter_extract_verify(
             &artifact,
             "git-0.01",
-            ["README"].into_iter(),
+            ["README", "Makefile"].into_iter(),
+            &["Make*"],
             dest.path(),
         )
         .expect("Expected to fetch, strip, filter, extract, and verify");
 
         let target_path = dest.path().join("README");
         assert!(target_path.exists());
+        assert!(!dest.path().join("Makefile").exists());
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let patterns = [
+            "src/**/testdata",
+            "src/**/*_test.go",
+            "pkg/tool/*",
+            "!pkg/tool/linux_amd64",
+        ];
+        for (path, excluded) in [
+            ("src/net/http/testdata/file.txt", true),
+            ("src/testdata", true),
+            ("src/net/http/server_test.go", true),
+            ("src/net/http/server.go", false),
+            ("src/net/http/test.go", false),
+            ("pkg/tool/linux_arm64/compile", true),
+            ("pkg/tool/linux_amd64/compile", false),
+            ("pkg/tool", false),
+            ("bin/go", false),
+        ] {
+            assert_eq!(is_excluded(Path::new(path), &patterns), excluded, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0.0 MiB");
+        assert_eq!(format_size(1024 * 1024 * 3 / 2), "1.5 MiB");
+        assert_eq!(format_size(70 * 1024 * 1024), "70.0 MiB");
     }
 }
@@ -0,0 +1,350 @@
+use crate::diag::Diagnosis;
+use crate::gomod::{GoMod, ModulePath};
+use std::fs;
+use std::path::Path;
+
+/// A module listed in `vendor/modules.txt`, from a
+/// `# {path} [{version}] [=> {path} [{version}]]` line and the
+/// `## explicit[; go {version}]` annotation that follows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VendoredModule {
+    pub(crate) module: ModulePath,
+    pub(crate) replacement: Option<ModulePath>,
+    pub(crate) explicit: bool,
+    pub(crate) go_version: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("Couldn't read vendor/modules.txt: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(
+        "The vendor directory is inconsistent with go.mod:\n\n{}\n\n{}",
+        .0.iter().map(|mismatch| format!("- {mismatch}")).collect::<Vec<_>>().join("\n"),
+        Diagnosis::InconsistentVendoring.guidance().trim_end()
+    )]
+    Inconsistent(Vec<String>),
+}
+
+/// Check that `vendor/modules.txt` in an app directory is consistent with
+/// `go.mod`, the way the `go` command does before building with `-mod=vendor`.
+///
+/// # Errors
+///
+/// Will return an error when `vendor/modules.txt` can't be read, or lists
+/// modules that don't match the `go.mod` requirements and replacements.
+pub(crate) fn check_vendor_dir(app_dir: &Path, gomod: &GoMod) -> Result<usize, Error> {
+    let vendored = parse_modules_txt(&fs::read_to_string(
+        app_dir.join("vendor").join("modules.txt"),
+    )?);
+    let mismatches = check_consistency(gomod, &vendored);
+    if mismatches.is_empty() {
+        Ok(vendored.len())
+    } else {
+        Err(Error::Inconsistent(mismatches))
+    }
+}
+
+/// Parse the module lines and annotations of `vendor/modules.txt`. Package
+/// lines and unknown annotations are ignored.
+pub(crate) fn parse_modules_txt(contents: &str) -> Vec<VendoredModule> {
+    let mut modules: Vec<VendoredModule> = vec![];
+    for line in contents.lines() {
+        if let Some(annotations) = line.strip_prefix("## ") {
+            let Some(module) = modules.last_mut() else {
+                continue;
+            };
+            for annotation in annotations.split(';').map(str::trim) {
+                if annotation == "explicit" {
+                    module.explicit = true;
+                } else if let Some(version) = annotation.strip_prefix("go ") {
+                    module.go_version = Some(version.trim().to_string());
+                }
+            }
+        } else if let Some(spec) = line.strip_prefix("# ") {
+            let fields = spec.split_whitespace().collect::<Vec<_>>();
+            let (old, new) = match fields.iter().position(|field| *field == "=>") {
+                Some(arrow) => (&fields[..arrow], Some(&fields[arrow + 1..])),
+                None => (fields.as_slice(), None),
+            };
+            let Some(module) = module_path(old) else {
+                continue;
+            };
+            modules.push(VendoredModule {
+                module,
+                replacement: new.and_then(module_path),
+                explicit: false,
+                go_version: None,
+            });
+        }
+    }
+    modules
+}
+
+fn module_path(fields: &[&str]) -> Option<ModulePath> {
+    match fields {
+        [path] => Some(ModulePath {
+            path: (*path).to_string(),
+            version: None,
+        }),
+        [path, version] => Some(ModulePath {
+            path: (*path).to_string(),
+            version: Some((*version).to_string()),
+        }),
+        _ => None,
+    }
+}
+
+/// Compare vendored modules with the `go.mod` requirements, replacements, and
+/// `go` directive, returning a description of each mismatch.
+pub(crate) fn check_consistency(gomod: &GoMod, vendored: &[VendoredModule]) -> Vec<String> {
+    let go_version = gomod.go.as_deref().and_then(go_version_parts);
+    // Go 1.14 started marking explicit requirements in `vendor/modules.txt`,
+    // Go 1.17 started recording unused wildcard replacements, and Go 1.21
+    // requires the go directive to be at least that of every dependency.
+    let check_explicit = go_version.is_some_and(|version| version >= (14, 0));
+    let check_wildcards = go_version.is_some_and(|version| version >= (17, 0));
+    let check_go_versions = go_version.is_some_and(|version| version >= (21, 0));
+    let find = |path: &str, version: Option<&str>| {
+        vendored
+            .iter()
+            .find(|m| m.module.path == path && m.module.version.as_deref() == version)
+    };
+
+    let mut mismatches = vec![];
+    for require in &gomod.require {
+        match find(&require.path, Some(&require.version)) {
+            None => {
+                let listed = vendored
+                    .iter()
+                    .filter(|m| m.module.path == require.path)
+                    .filter_map(|m| m.module.version.clone())
+                    .collect::<Vec<_>>();
+                // Before Go 1.14, only modules that provide vendored
+                // packages are listed.
+                if listed.is_empty() {
+                    if check_explicit {
+                        mismatches.push(format!(
+                            "{}@{} is required in go.mod, but missing from vendor/modules.txt",
+                            require.path, require.version
+                        ));
+                    }
+                } else {
+                    mismatches.push(format!(
+                        "{}@{} is required in go.mod, but vendor/modules.txt has {}",
+                        require.path,
+                        require.version,
+                        listed.join(", ")
+                    ));
+                }
+            }
+            Some(module) if check_explicit && !module.explicit => {
+                mismatches.push(format!(
+                    "{}@{} is required in go.mod, but not marked as explicit in vendor/modules.txt",
+                    require.path, require.version
+                ));
+            }
+            Some(_) => (),
+        }
+    }
+
+    for module in vendored.iter().filter(|m| m.explicit) {
+        if !gomod.require.iter().any(|require| {
+            require.path == module.module.path
+                && Some(&require.version) == module.module.version.as_ref()
+        }) {
+            mismatches.push(format!(
+                "{} is marked as explicit in vendor/modules.txt, but not required in go.mod",
+                display(&module.module)
+            ));
+        }
+    }
+
+    for replace in &gomod.replace {
+        match find(&replace.old.path, replace.old.version.as_deref())
+            .and_then(|m| m.replacement.as_ref())
+        {
+            Some(replacement) if *replacement == replace.new => (),
+            Some(replacement) => mismatches.push(format!(
+                "{} is replaced by {} in go.mod, but by {} in vendor/modules.txt",
+                display(&replace.old),
+                display(&replace.new),
+                display(replacement)
+            )),
+            // Unused wildcard replacements aren't recorded before Go 1.17.
+            None if replace.old.version.is_none() && !check_wildcards => (),
+            None => mismatches.push(format!(
+                "{} is replaced in go.mod, but not marked as replaced in vendor/modules.txt",
+                display(&replace.old)
+            )),
+        }
+    }
+
+    if check_go_versions {
+        for module in vendored {
+            if let Some(required) = &module.go_version
+                && go_version_parts(required) > go_version
+            {
+                mismatches.push(format!(
+                    "{} requires go {required}, but go.mod has go {}",
+                    display(&module.module),
+                    gomod.go.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+    }
+
+    for module in vendored.iter().filter(|m| m.replacement.is_some()) {
+        if !gomod.replace.iter().any(|replace| {
+            replace.old.path == module.module.path
+                && (replace.old.version.is_none() || replace.old.version == module.module.version)
+        }) {
+            mismatches.push(format!(
+                "{} is marked as replaced in vendor/modules.txt, but not replaced in go.mod",
+                display(&module.module)
+            ));
+        }
+    }
+    mismatches
+}
+
+fn display(module: &ModulePath) -> String {
+    match &module.version {
+        Some(version) => format!("{}@{version}", module.path),
+        None => module.path.clone(),
+    }
+}
+
+/// The minor and patch numbers of a `1.x[.y]` go version, ignoring any
+/// pre-release suffix.
+fn go_version_parts(version: &str) -> Option<(u64, u64)> {
+    let number = |part: Option<&str>| -> Option<u64> {
+        part?
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .ok()
+    };
+    let mut parts = version.strip_prefix("1.")?.split('.');
+    Some((number(parts.next())?, number(parts.next()).unwrap_or(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GO_MOD: &str = "\
+module example.com/app
+
+go 1.22
+
+require (
+\tgithub.com/gin-gonic/gin v1.9.1
+\tgithub.com/pkg/errors v0.9.1
+\tgolang.org/x/net v0.20.0 // indirect
+)
+
+replace github.com/pkg/errors => ../errors
+";
+
+    const MODULES_TXT: &str = "\
+# github.com/gin-gonic/gin v1.9.1
+## explicit; go 1.20
+github.com/gin-gonic/gin
+# github.com/pkg/errors v0.9.1 => ../errors
+## explicit
+github.com/pkg/errors
+# golang.org/x/net v0.20.0
+## explicit; go 1.18
+golang.org/x/net/html
+# golang.org/x/text v0.14.0
+## go 1.18
+# github.com/pkg/errors => ../errors
+";
+
+    #[test]
+    fn parse_modules_txt_lines() {
+        let modules = parse_modules_txt(MODULES_TXT);
+        assert_eq!(modules.len(), 5);
+        assert_eq!(
+            modules[1],
+            VendoredModule {
+                module: ModulePath {
+                    path: "github.com/pkg/errors".to_string(),
+                    version: Some("v0.9.1".to_string()),
+                },
+                replacement: Some(ModulePath {
+                    path: "../errors".to_string(),
+                    version: None,
+                }),
+                explicit: true,
+                go_version: None,
+            }
+        );
+        assert!(!modules[3].explicit);
+        assert_eq!(modules[3].go_version.as_deref(), Some("1.18"));
+        assert_eq!(modules[4].module.version, None);
+    }
+
+    #[test]
+    fn check_consistent_vendoring() {
+        for go_version in ["go 1.16", "go 1.22"] {
+            let gomod = GoMod::parse(&GO_MOD.replace("go 1.22", go_version));
+            assert_eq!(
+                check_consistency(&gomod, &parse_modules_txt(MODULES_TXT)),
+                Vec::<String>::new()
+            );
+        }
+    }
+
+    #[test]
+    fn check_pre_go_1_14_vendoring() {
+        let modules_txt = "\
+# github.com/gin-gonic/gin v1.9.1
+github.com/gin-gonic/gin
+# github.com/pkg/errors v0.9.1 => ../errors
+github.com/pkg/errors
+";
+        let gomod = GoMod::parse(&GO_MOD.replace("go 1.22", "go 1.13"));
+        assert_eq!(
+            check_consistency(&gomod, &parse_modules_txt(modules_txt)),
+            Vec::<String>::new()
+        );
+        let gomod = GoMod::parse(
+            &GO_MOD
+                .replace("go 1.22", "go 1.13")
+                .replace("v1.9.1", "v1.10.0"),
+        );
+        assert_eq!(
+            check_consistency(&gomod, &parse_modules_txt(modules_txt)),
+            [
+                "github.com/gin-gonic/gin@v1.10.0 is required in go.mod, but vendor/modules.txt has v1.9.1"
+            ]
+        );
+    }
+
+    #[test]
+    fn check_inconsistent_vendoring() {
+        let gomod = GoMod::parse(&GO_MOD.replace("v1.9.1", "v1.10.0").replace(
+            "github.com/pkg/errors => ../errors",
+            "golang.org/x/text => ../text",
+        ));
+        assert_eq!(
+            check_consistency(
+                &gomod,
+                &parse_modules_txt(
+                    &MODULES_TXT.replace("explicit; go 1.18", "explicit; go 1.22.1")
+                )
+            ),
+            [
+                "github.com/gin-gonic/gin@v1.10.0 is required in go.mod, but vendor/modules.txt has v1.9.1",
+                "github.com/gin-gonic/gin@v1.9.1 is marked as explicit in vendor/modules.txt, but not required in go.mod",
+                "golang.org/x/text is replaced in go.mod, but not marked as replaced in vendor/modules.txt",
+                "golang.org/x/net@v0.20.0 requires go 1.22.1, but go.mod has go 1.22",
+                "github.com/pkg/errors@v0.9.1 is marked as replaced in vendor/modules.txt, but not replaced in go.mod",
+                "github.com/pkg/errors is marked as replaced in vendor/modules.txt, but not replaced in go.mod",
+            ]
+        );
+    }
+}
@@ -0,0 +1,774 @@
+use crate::buildinfo::BuildInfo;
+use serde::Deserialize;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The CNB binding type for a Go vulnerability database.
+pub(crate) const BINDING_TYPE: &str = "govulndb";
+
+/// The module path the Go vulnerability database uses for the standard
+/// library.
+const STDLIB_MODULE: &str = "stdlib";
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("Couldn't read vulnerability database at {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Couldn't read go.sum: {0}")]
+    GoSum(std::io::Error),
+    #[error(
+        "Found no readable OSV entries in vulnerability database at {0}, skipped {1} unreadable files"
+    )]
+    Empty(PathBuf, usize),
+    #[error("Found {count} known vulnerabilities with {threshold} severity or higher")]
+    Threshold { count: usize, threshold: Severity },
+}
+
+/// Vulnerability severity, as reported in the `database_specific.severity`
+/// field of OSV entries, or else rated from their CVSS v3 vectors. Entries
+/// without either, which includes all entries of the Go vulnerability
+/// database, are `Unknown`, which ranks lowest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Unknown,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "unknown" => Ok(Severity::Unknown),
+            "low" => Ok(Severity::Low),
+            "moderate" | "medium" => Ok(Severity::Moderate),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!(
+                "unknown severity {value}, expected one of: unknown, low, moderate, high, critical"
+            )),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Severity::Unknown => "UNKNOWN",
+                Severity::Low => "LOW",
+                Severity::Moderate => "MODERATE",
+                Severity::High => "HIGH",
+                Severity::Critical => "CRITICAL",
+            }
+        )
+    }
+}
+
+/// A module version to check for vulnerabilities.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ModuleVersion {
+    pub(crate) path: String,
+    pub(crate) version: String,
+}
+
+/// A known vulnerability affecting a module version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Finding {
+    pub(crate) id: String,
+    pub(crate) aliases: Vec<String>,
+    pub(crate) summary: Option<String>,
+    pub(crate) severity: Severity,
+    pub(crate) module: ModuleVersion,
+    pub(crate) fixed: Vec<String>,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}) in {} {}",
+            self.id, self.severity, self.module.path, self.module.version
+        )?;
+        if !self.aliases.is_empty() {
+            write!(f, " [{}]", self.aliases.join(", "))?;
+        }
+        if let Some(summary) = &self.summary {
+            write!(f, ": {summary}")?;
+        }
+        if self.fixed.is_empty() {
+            write!(f, ". No fixed version is available.")
+        } else {
+            write!(f, ". Fixed in: {}", self.fixed.join(", "))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvEntry {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    summary: Option<String>,
+    #[serde(default)]
+    affected: Vec<OsvAffected>,
+    #[serde(default)]
+    severity: Vec<OsvSeverity>,
+    database_specific: Option<OsvDatabaseSpecific>,
+}
+
+impl OsvEntry {
+    fn severity(&self) -> Severity {
+        self.database_specific
+            .as_ref()
+            .and_then(|specific| specific.severity.as_deref())
+            .and_then(|severity| severity.parse().ok())
+            .or_else(|| {
+                self.severity
+                    .iter()
+                    .filter(|severity| severity.severity_type == "CVSS_V3")
+                    .filter_map(|severity| cvss_v3_severity(&severity.score))
+                    .max()
+            })
+            .unwrap_or(Severity::Unknown)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvSeverity {
+    #[serde(rename = "type")]
+    severity_type: String,
+    score: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvAffected {
+    package: OsvPackage,
+    #[serde(default)]
+    ranges: Vec<OsvRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvPackage {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvRange {
+    #[serde(rename = "type")]
+    range_type: String,
+    #[serde(default)]
+    events: Vec<OsvEvent>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvEvent {
+    introduced: Option<String>,
+    fixed: Option<String>,
+    last_affected: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvDatabaseSpecific {
+    severity: Option<String>,
+}
+
+/// A Go vulnerability database in OSV JSON format, read from a local
+/// directory.
+pub(crate) struct VulnDb {
+    entries: Vec<OsvEntry>,
+    unreadable: usize,
+}
+
+impl VulnDb {
+    /// Read every OSV entry in a directory, recursively. JSON files that
+    /// aren't OSV entries (without an `id`), such as the database index
+    /// files, are skipped. Files that aren't valid JSON or OSV entries are
+    /// counted as unreadable.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error when the directory or a file cannot be read, or
+    /// there are no readable entries.
+    pub(crate) fn read(dir: &Path) -> Result<Self, Error> {
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |error| Error::Io(path, error)
+        };
+        let mut entries = vec![];
+        let mut unreadable = 0;
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir).map_err(io_error(&dir))? {
+                let path = entry.map_err(io_error(&dir))?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|ext| ext == "json") {
+                    let contents = fs::read(&path).map_err(io_error(&path))?;
+                    match serde_json::from_slice::<serde_json::Value>(&contents) {
+                        Ok(value) if value.get("id").is_none() => (),
+                        Ok(value) => match serde_json::from_value::<OsvEntry>(value) {
+                            Ok(entry) => entries.push(entry),
+                            Err(_) => unreadable += 1,
+                        },
+                        Err(_) => unreadable += 1,
+                    }
+                }
+            }
+        }
+        if entries.is_empty() {
+            return Err(Error::Empty(dir.to_path_buf(), unreadable));
+        }
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(VulnDb {
+            entries,
+            unreadable,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The number of JSON files that couldn't be read as OSV entries.
+    pub(crate) fn unreadable(&self) -> usize {
+        self.unreadable
+    }
+
+    /// Find the known vulnerabilities affecting a list of module versions.
+    pub(crate) fn scan(&self, modules: &[ModuleVersion]) -> Vec<Finding> {
+        let mut findings = vec![];
+        for module in modules {
+            for entry in &self.entries {
+                let mut fixed = vec![];
+                let mut is_affected = false;
+                for affected in entry.affected.iter().filter(|affected| {
+                    affected.package.ecosystem == "Go" && affected.package.name == module.path
+                }) {
+                    if affected_version(affected, &module.version) {
+                        is_affected = true;
+                        fixed.extend(fixed_versions(affected));
+                    }
+                }
+                if is_affected {
+                    findings.push(Finding {
+                        id: entry.id.clone(),
+                        aliases: entry.aliases.clone(),
+                        summary: entry.summary.clone(),
+                        severity: entry.severity(),
+                        module: module.clone(),
+                        fixed,
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// Find a vulnerability database provided by a CNB binding of type
+/// `govulndb`, in the service binding root directory.
+pub(crate) fn find_binding(binding_root: &Path) -> Option<PathBuf> {
+    let mut bindings = fs::read_dir(binding_root)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    bindings.sort();
+    bindings.into_iter().find(|binding| {
+        fs::read_to_string(binding.join("type"))
+            .is_ok_and(|binding_type| binding_type.trim() == BINDING_TYPE)
+    })
+}
+
+/// The modules embedded in built binaries, and the standard library of the
+/// Go version they were built with.
+pub(crate) fn binary_modules(build_info: &[BuildInfo]) -> Vec<ModuleVersion> {
+    let mut modules = vec![];
+    for info in build_info {
+        modules.push(ModuleVersion {
+            path: STDLIB_MODULE.to_string(),
+            version: info.go_version.clone(),
+        });
+        modules.extend(info.deps.iter().map(|dep| ModuleVersion {
+            path: dep.built().path.clone(),
+            version: dep.built().version.clone(),
+        }));
+    }
+    modules.sort();
+    modules.dedup();
+    modules
+}
+
+/// The module versions listed in a `go.sum` file. This includes modules
+/// that are required by the build, but may not be linked into any binary.
+///
+/// # Errors
+///
+/// Will return an error when the file cannot be read.
+pub(crate) fn gosum_modules(path: &Path) -> Result<Vec<ModuleVersion>, Error> {
+    let mut modules = fs::read_to_string(path)
+        .map_err(Error::GoSum)?
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(path), Some(version)) if !version.ends_with("/go.mod") => {
+                    Some(ModuleVersion {
+                        path: path.to_string(),
+                        version: version.to_string(),
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    modules.sort();
+    modules.dedup();
+    Ok(modules)
+}
+
+/// Check findings against a failure threshold. Findings of unknown severity
+/// rank lowest, so they only count for an `unknown` threshold.
+///
+/// # Errors
+///
+/// Will return an error when any finding has a severity at or above the
+/// threshold.
+pub(crate) fn check_threshold(findings: &[Finding], threshold: Severity) -> Result<(), Error> {
+    let count = findings
+        .iter()
+        .filter(|finding| finding.severity >= threshold)
+        .count();
+    if count == 0 {
+        Ok(())
+    } else {
+        Err(Error::Threshold { count, threshold })
+    }
+}
+
+/// Whether a version is affected, according to the explicit versions and
+/// `SEMVER` ranges of an OSV `affected` entry. Range events are sorted by
+/// version, then evaluated in order, as described by the OSV schema.
+fn affected_version(affected: &OsvAffected, version: &str) -> bool {
+    let Some(version) = semver_version(version) else {
+        return false;
+    };
+    if affected
+        .versions
+        .iter()
+        .filter_map(|affected| semver_version(affected))
+        .any(|affected| affected == version)
+    {
+        return true;
+    }
+    affected
+        .ranges
+        .iter()
+        .filter(|range| range.range_type == "SEMVER")
+        .any(|range| {
+            let mut events = range
+                .events
+                .iter()
+                .filter_map(|event| Some((event_version(event)?, event)))
+                .collect::<Vec<_>>();
+            events.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut is_affected = false;
+            for (_, event) in events {
+                let compare = |event_version: &String| semver_version(event_version);
+                if let Some(introduced) = event.introduced.as_ref().and_then(compare)
+                    && version >= introduced
+                {
+                    is_affected = true;
+                }
+                if let Some(fixed) = event.fixed.as_ref().and_then(compare)
+                    && version >= fixed
+                {
+                    is_affected = false;
+                }
+                if let Some(last_affected) = event.last_affected.as_ref().and_then(compare)
+                    && version > last_affected
+                {
+                    is_affected = false;
+                }
+            }
+            is_affected
+        })
+}
+
+/// The version of an OSV range event, whichever kind of event it is.
+fn event_version(event: &OsvEvent) -> Option<semver::Version> {
+    [&event.introduced, &event.fixed, &event.last_affected]
+        .into_iter()
+        .flatten()
+        .find_map(|version| semver_version(version))
+}
+
+/// Rate a CVSS v3 vector (e.g. `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`)
+/// by its base score, as specified by CVSS v3.1. Returns `None` for invalid
+/// vectors and a score of 0.
+fn cvss_v3_severity(vector: &str) -> Option<Severity> {
+    let mut parts = vector.split('/');
+    if !parts.next()?.starts_with("CVSS:3") {
+        return None;
+    }
+    let metrics = parts
+        .filter_map(|part| part.split_once(':'))
+        .collect::<Vec<_>>();
+    let metric = |name: &str| {
+        metrics
+            .iter()
+            .find(|(metric, _)| *metric == name)
+            .map(|(_, value)| *value)
+    };
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let attack_complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges_required = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let user_interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let impact_metric = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let iss = 1.0
+        - (1.0 - impact_metric("C")?) * (1.0 - impact_metric("I")?) * (1.0 - impact_metric("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * f64::powi(iss - 0.02, 15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return None;
+    }
+    let exploitability =
+        8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+    let score = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(match round_up(score.min(10.0)) {
+        ..4.0 => Severity::Low,
+        ..7.0 => Severity::Moderate,
+        ..9.0 => Severity::High,
+        _ => Severity::Critical,
+    })
+}
+
+/// Round up to one decimal, as specified by CVSS v3.1 to avoid floating
+/// point errors.
+fn round_up(value: f64) -> f64 {
+    // Scores are at most 10.8, so this can't truncate.
+    #[allow(clippy::cast_possible_truncation)]
+    let int_value = (value * 100_000.0).round() as i32;
+    if int_value % 10_000 == 0 {
+        f64::from(int_value) / 100_000.0
+    } else {
+        f64::from(int_value / 10_000 + 1) / 10.0
+    }
+}
+
+fn fixed_versions(affected: &OsvAffected) -> Vec<String> {
+    affected
+        .ranges
+        .iter()
+        .flat_map(|range| &range.events)
+        .filter_map(|event| event.fixed.clone())
+        .collect()
+}
+
+/// Parse a Go module or toolchain version (e.g. `v1.2.3`, `go1.22`) as a
+/// semantic version.
+fn semver_version(version: &str) -> Option<semver::Version> {
+    let version = version
+        .strip_prefix("go")
+        .or_else(|| version.strip_prefix('v'))
+        .unwrap_or(version);
+    semver::Version::parse(version).ok().or_else(|| {
+        // Go toolchain versions may omit the patch version.
+        let (core, pre) = version
+            .find(|c: char| c.is_ascii_alphabetic())
+            .map_or((version, ""), |index| version.split_at(index));
+        let mut parts = core.split('.').collect::<Vec<_>>();
+        if parts.len() >= 3 {
+            return None;
+        }
+        parts.resize(3, "0");
+        let pre = if pre.is_empty() {
+            String::new()
+        } else {
+            format!("-{pre}")
+        };
+        semver::Version::parse(&format!("{}{pre}", parts.join("."))).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildinfo::parse_go_version_m;
+
+    const GIN_VULN: &str = r#"{
+        "id": "GO-2023-1737",
+        "aliases": ["CVE-2023-29401", "GHSA-2c4m-59x9-fr2g"],
+        "summary": "Improper handling of filenames in Content-Disposition HTTP header in github.com/gin-gonic/gin",
+        "affected": [{
+            "package": {"name": "github.com/gin-gonic/gin", "ecosystem": "Go"},
+            "ranges": [{"type": "SEMVER", "events": [{"introduced": "1.3.1-0.20190301021747-ccb9e902956d"}, {"fixed": "1.9.1"}]}]
+        }],
+        "database_specific": {"severity": "MODERATE"}
+    }"#;
+
+    const STDLIB_VULN: &str = r#"{
+        "id": "GO-2024-2598",
+        "summary": "Verify panics on certificates with an unknown public key algorithm in crypto/x509",
+        "affected": [{
+            "package": {"name": "stdlib", "ecosystem": "Go"},
+            "ranges": [{"type": "SEMVER", "events": [
+                {"introduced": "0"}, {"fixed": "1.21.8"},
+                {"introduced": "1.22.0-0"}, {"fixed": "1.22.1"}
+            ]}]
+        }]
+    }"#;
+
+    fn vuln_db() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("ID")).unwrap();
+        fs::create_dir_all(dir.path().join("index")).unwrap();
+        fs::write(dir.path().join("ID/GO-2023-1737.json"), GIN_VULN).unwrap();
+        fs::write(dir.path().join("ID/GO-2024-2598.json"), STDLIB_VULN).unwrap();
+        fs::write(
+            dir.path().join("index/modules.json"),
+            r#"[{"path": "stdlib"}]"#,
+        )
+        .unwrap();
+        dir
+    }
+
+    fn module(path: &str, version: &str) -> ModuleVersion {
+        ModuleVersion {
+            path: path.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn scan_binary_modules() {
+        let dir = vuln_db();
+        let db = VulnDb::read(dir.path()).unwrap();
+        assert_eq!(db.len(), 2);
+
+        let build_info = parse_go_version_m(
+            "/layers/go_target/bin/server: go1.21.7\n\
+             \tdep\tgithub.com/gin-gonic/gin\tv1.9.0\th1:abc=\n\
+             \tdep\tgolang.org/x/text\tv0.14.0\th1:def=\n",
+        );
+        let findings = db.scan(&binary_modules(&build_info));
+        assert_eq!(
+            findings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "GO-2023-1737 (MODERATE) in github.com/gin-gonic/gin v1.9.0 [CVE-2023-29401, GHSA-2c4m-59x9-fr2g]: \
+                Improper handling of filenames in Content-Disposition HTTP header in github.com/gin-gonic/gin. \
+                Fixed in: 1.9.1",
+                "GO-2024-2598 (UNKNOWN) in stdlib go1.21.7: Verify panics on certificates with an unknown \
+                public key algorithm in crypto/x509. Fixed in: 1.21.8, 1.22.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_stdlib_with_experiments() {
+        let dir = vuln_db();
+        let db = VulnDb::read(dir.path()).unwrap();
+        let build_info = parse_go_version_m("/layers/go_target/bin/server: go1.22.0 X:rangefunc\n");
+        let findings = db.scan(&binary_modules(&build_info));
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| (finding.id.as_str(), finding.module.version.as_str()))
+                .collect::<Vec<_>>(),
+            [("GO-2024-2598", "go1.22.0")]
+        );
+    }
+
+    #[test]
+    fn scan_unaffected_versions() {
+        let dir = vuln_db();
+        let db = VulnDb::read(dir.path()).unwrap();
+        let findings = db.scan(&[
+            module("github.com/gin-gonic/gin", "v1.9.1"),
+            module("github.com/gin-gonic/gin", "v1.3.0"),
+            module("stdlib", "go1.21.8"),
+            module("stdlib", "go1.22.1"),
+            module("example.com/app", "(devel)"),
+        ]);
+        assert!(findings.is_empty(), "{findings:?}");
+
+        let findings = db.scan(&[module("stdlib", "go1.22")]);
+        assert_eq!(findings.len(), 1);
+    }
+
+    #[test]
+    fn read_gosum_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let gosum = dir.path().join("go.sum");
+        fs::write(
+            &gosum,
+            "github.com/gin-gonic/gin v1.9.0 h1:abc=\n\
+             github.com/gin-gonic/gin v1.9.0/go.mod h1:def=\n\
+             github.com/gin-gonic/gin v1.8.0/go.mod h1:ghi=\n",
+        )
+        .unwrap();
+        assert_eq!(
+            gosum_modules(&gosum).unwrap(),
+            [module("github.com/gin-gonic/gin", "v1.9.0")]
+        );
+    }
+
+    #[test]
+    fn threshold() {
+        let dir = vuln_db();
+        let db = VulnDb::read(dir.path()).unwrap();
+        let findings = db.scan(&[module("github.com/gin-gonic/gin", "v1.9.0")]);
+        assert!(check_threshold(&findings, Severity::High).is_ok());
+        assert_eq!(
+            check_threshold(&findings, Severity::Moderate)
+                .unwrap_err()
+                .to_string(),
+            "Found 1 known vulnerabilities with MODERATE severity or higher"
+        );
+
+        // Entries of the Go vulnerability database have no severity, so they
+        // only fail the build for an `unknown` threshold.
+        let findings = db.scan(&[module("stdlib", "go1.22.0")]);
+        assert_eq!(findings[0].severity, Severity::Unknown);
+        assert!(check_threshold(&findings, Severity::Low).is_ok());
+        assert!(check_threshold(&findings, Severity::Unknown).is_err());
+    }
+
+    #[test]
+    fn affected_version_with_unsorted_events() {
+        let affected: OsvAffected = serde_json::from_str(
+            r#"{
+                "package": {"ecosystem": "Go", "name": "example.com/mod"},
+                "ranges": [{"type": "SEMVER", "events": [
+                    {"fixed": "1.9.1"},
+                    {"last_affected": "2.1.0"},
+                    {"introduced": "2.0.0"},
+                    {"introduced": "1.3.0"}
+                ]}]
+            }"#,
+        )
+        .unwrap();
+        for (version, expected) in [
+            ("v1.2.0", false),
+            ("v1.3.0", true),
+            ("v1.9.0", true),
+            ("v1.9.1", false),
+            ("v2.1.0", true),
+            ("v2.1.1", false),
+        ] {
+            assert_eq!(affected_version(&affected, version), expected, "{version}");
+        }
+    }
+
+    #[test]
+    fn severity_from_cvss_vectors() {
+        for (vector, expected) in [
+            (
+                "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H",
+                Some(Severity::Critical),
+            ),
+            (
+                "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H",
+                Some(Severity::High),
+            ),
+            (
+                "CVSS:3.0/AV:N/AC:H/PR:N/UI:R/S:C/C:L/I:L/A:N",
+                Some(Severity::Moderate),
+            ),
+            (
+                "CVSS:3.1/AV:L/AC:H/PR:H/UI:R/S:U/C:L/I:N/A:N",
+                Some(Severity::Low),
+            ),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N", None),
+            ("CVSS:2.0/AV:N/AC:L/Au:N/C:P/I:P/A:P", None),
+            ("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", None),
+        ] {
+            assert_eq!(cvss_v3_severity(vector), expected, "{vector}");
+        }
+
+        let entry = serde_json::from_str::<OsvEntry>(
+            r#"{"id": "GHSA-1", "severity": [{"type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.severity(), Severity::High);
+    }
+
+    #[test]
+    fn read_unreadable_entries() {
+        let dir = vuln_db();
+        fs::write(dir.path().join("ID/GO-2024-0001.json"), "{\"id\": 1}").unwrap();
+        fs::write(dir.path().join("ID/GO-2024-0002.json"), "not json").unwrap();
+        let db = VulnDb::read(dir.path()).unwrap();
+        assert_eq!((db.len(), db.unreadable()), (2, 2));
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("GO-2024-0002.json"), "not json").unwrap();
+        assert_eq!(
+            VulnDb::read(dir.path()).err().unwrap().to_string(),
+            format!(
+                "Found no readable OSV entries in vulnerability database at {}, skipped 1 unreadable files",
+                dir.path().display()
+            )
+        );
+    }
+
+    #[test]
+    fn find_vulndb_binding() {
+        let root = tempfile::tempdir().unwrap();
+        for (name, binding_type) in [("ca-certs", "ca-certificates"), ("vulns", "govulndb\n")] {
+            fs::create_dir_all(root.path().join(name)).unwrap();
+            fs::write(root.path().join(name).join("type"), binding_type).unwrap();
+        }
+        assert_eq!(find_binding(root.path()), Some(root.path().join("vulns")));
+        assert_eq!(find_binding(&root.path().join("missing")), None);
+    }
+}